@@ -0,0 +1,86 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+use super::{Error, Model, ParseOptions, Reader, Result, Warning};
+
+/// A future which reads the whole of an asynchronous reader into memory and
+/// then parses it.
+///
+/// At most one byte more than `max_total_bytes` is read, which is enough for
+/// the parser to report the limit being exceeded in the section where it
+/// happens.
+struct ReadModel<R, T> {
+    rdr: R,
+    bytes: Vec<u8>,
+    options: ParseOptions,
+    /// Build the output from the model and any warnings.
+    finish: fn(Model, Vec<Warning>) -> T,
+}
+
+impl<R: AsyncRead + Unpin, T> Future for ReadModel<R, T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let mut chunk = [0; 8192];
+        let budget = this.options.limits.max_total_bytes.saturating_add(1);
+        let offset = this.bytes.len() as u64;
+        if offset < budget {
+            let len = (budget - offset).min(chunk.len() as u64) as usize;
+            let mut buf = ReadBuf::new(&mut chunk[..len]);
+            match Pin::new(&mut this.rdr).poll_read(cx, &mut buf) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(error)) => return Poll::Ready(Err(Error::Io { offset, error })),
+                Poll::Ready(Ok(())) if buf.filled().is_empty() => {}
+                Poll::Ready(Ok(())) => {
+                    this.bytes.extend_from_slice(buf.filled());
+                    // Yield after each chunk, so a reader which is always
+                    // ready does not hold up other tasks.
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+            }
+        }
+        let options = this.options.clone();
+        let result = Reader::from_slice(&this.bytes, options).read_model_with_warnings();
+        Poll::Ready(result.map(|(model, warnings)| (this.finish)(model, warnings)))
+    }
+}
+
+impl Model {
+    /// Read an ms3d model file from an asynchronous reader.
+    ///
+    /// The file is read into memory without blocking and then parsed the same
+    /// way as [`Model::from_bytes`](struct.Model.html#method.from_bytes).
+    pub fn from_async_reader<R>(rdr: R) -> impl Future<Output = Result<Self>>
+    where
+        R: AsyncRead + Unpin,
+    {
+        ReadModel {
+            rdr,
+            bytes: Vec::new(),
+            options: ParseOptions::default(),
+            finish: |model, _| model,
+        }
+    }
+
+    /// Read an ms3d model file from an asynchronous reader with the given
+    /// options, returning any warnings for problems that were ignored.
+    pub fn from_async_reader_with<R>(
+        rdr: R,
+        options: ParseOptions,
+    ) -> impl Future<Output = Result<(Self, Vec<Warning>)>>
+    where
+        R: AsyncRead + Unpin,
+    {
+        ReadModel {
+            rdr,
+            bytes: Vec::new(),
+            options,
+            finish: |model, warnings| (model, warnings),
+        }
+    }
+}
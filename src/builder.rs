@@ -0,0 +1,269 @@
+use std::path::PathBuf;
+
+use super::model::*;
+use super::repair::reference_counts;
+use super::{Error, Result, Section};
+
+/// Builds a [`Model`](struct.Model.html) one element at a time, keeping the
+/// indices between elements consistent.
+///
+/// Each `add_*` method returns the index of the new element, to be passed to
+/// the methods which refer to it. Fields derived from other elements, such as
+/// [`Vertex::reference_count`](struct.Vertex.html#structfield.reference_count)
+/// and [`Triangle::group_index`](struct.Triangle.html#structfield.group_index),
+/// are filled in automatically.
+///
+/// The built model is a version 4 model with every optional section present
+/// and filled in with default values.
+#[derive(Clone, Debug)]
+pub struct ModelBuilder {
+    model: Model,
+}
+
+impl ModelBuilder {
+    pub fn new() -> Self {
+        ModelBuilder {
+            model: Model::default(),
+        }
+    }
+
+    /// Add a vertex, optionally attached to a joint added with
+    /// [`add_joint`](#method.add_joint).
+    pub fn add_vertex(&mut self, position: [f32; 3], joint: Option<usize>) -> Result<usize> {
+        let bone_id = match joint {
+            Some(joint) => {
+                self.check_index(Section::Joints, joint)?;
+                joint as i8
+            }
+            None => -1,
+        };
+        let index = self.check_len(Section::Vertices, u16::MAX as usize)?;
+        self.model.vertices.push(Vertex {
+            flags: Flags::empty(),
+            vertex: position,
+            bone_id,
+            reference_count: 0,
+        });
+        Ok(index)
+    }
+
+    /// Add a triangle to a group added with [`add_group`](#method.add_group),
+    /// with a normal and texture coordinates for each corner.
+    ///
+    /// The triangle is put in smoothing group 1.
+    pub fn add_triangle(
+        &mut self,
+        group: usize,
+        vertices: [usize; 3],
+        normals: [[f32; 3]; 3],
+        uvs: [[f32; 2]; 3],
+    ) -> Result<usize> {
+        self.check_index(Section::Groups, group)?;
+        for &vertex in &vertices {
+            self.check_index(Section::Vertices, vertex)?;
+        }
+        let index = self.check_len(Section::Triangles, u16::MAX as usize)?;
+        self.model.triangles.push(Triangle {
+            flags: Flags::empty(),
+            vertex_indices: [vertices[0] as u16, vertices[1] as u16, vertices[2] as u16],
+            vertex_normals: normals,
+            s: [uvs[0][0], uvs[1][0], uvs[2][0]],
+            t: [uvs[0][1], uvs[1][1], uvs[2][1]],
+            smoothing_group: 1,
+            group_index: group as u8,
+        });
+        self.model.groups[group].triangle_indices.push(index as u16);
+        Ok(index)
+    }
+
+    /// Add a group, optionally using a material added with
+    /// [`add_material`](#method.add_material).
+    pub fn add_group(&mut self, name: &str, material: Option<usize>) -> Result<usize> {
+        let material_index = match material {
+            Some(material) => {
+                self.check_index(Section::Materials, material)?;
+                material as i8
+            }
+            None => -1,
+        };
+        // Triangles store the index of their group in a single byte.
+        let index = self.check_len(Section::Groups, u8::MAX as usize + 1)?;
+        self.model.groups.push(Group {
+            flags: Flags::empty(),
+            name: name.to_owned(),
+            raw_name: Vec::new(),
+            triangle_indices: Vec::new(),
+            material_index,
+        });
+        Ok(index)
+    }
+
+    /// Add a material with MilkShape's default colours and the given texture,
+    /// which may be empty.
+    pub fn add_material(&mut self, name: &str, texture: PathBuf) -> Result<usize> {
+        let index = self.check_len(Section::Materials, i8::MAX as usize + 1)?;
+        self.model.materials.push(Material {
+            name: name.to_owned(),
+            raw_name: Vec::new(),
+            ambient: [0.2, 0.2, 0.2, 1.0],
+            diffuse: [0.8, 0.8, 0.8, 1.0],
+            specular: [0.0, 0.0, 0.0, 1.0],
+            emissive: [0.0, 0.0, 0.0, 1.0],
+            shininess: 0.0,
+            transparency: 1.0,
+            mode: 0,
+            texture,
+            raw_texture: Vec::new(),
+            alphamap: PathBuf::new(),
+            raw_alphamap: Vec::new(),
+        });
+        Ok(index)
+    }
+
+    /// Add a joint with no key frames, optionally the child of a joint which
+    /// has already been added.
+    pub fn add_joint(&mut self, name: &str, parent: Option<usize>) -> Result<usize> {
+        let parent_name = match parent {
+            Some(parent) => {
+                self.check_index(Section::Joints, parent)?;
+                self.model.joints[parent].name.clone()
+            }
+            None => String::new(),
+        };
+        // Vertices store the index of their joint in a signed byte.
+        let index = self.check_len(Section::Joints, i8::MAX as usize + 1)?;
+        self.model.joints.push(Joint {
+            flags: Flags::empty(),
+            name: name.to_owned(),
+            raw_name: Vec::new(),
+            parent_name,
+            raw_parent_name: Vec::new(),
+            rotation: [0.0; 3],
+            position: [0.0; 3],
+            key_frames_rot: Vec::new(),
+            key_frames_trans: Vec::new(),
+        });
+        Ok(index)
+    }
+
+    /// Mutable access to the model being built, for setting fields which have
+    /// no builder method.
+    pub fn model_mut(&mut self) -> &mut Model {
+        &mut self.model
+    }
+
+    /// Finish the model, filling in the reference counts of the vertices and
+    /// any optional sections which were not set through
+    /// [`model_mut`](#method.model_mut). The vertex and joint extra info are
+    /// resized to match the vertices and joints, padding them with default
+    /// values.
+    pub fn build(self) -> Model {
+        let mut model = self.model;
+        let counts = reference_counts(model.vertices.len(), &model.triangles);
+        for (vertex, count) in model.vertices.iter_mut().zip(counts) {
+            vertex.reference_count = count;
+        }
+
+        if model.comments.is_none() {
+            model.comments = Some(Comments {
+                sub_version: 1,
+                group_comments: Vec::new(),
+                material_comments: Vec::new(),
+                joint_comments: Vec::new(),
+                model_comment: None,
+            });
+        }
+
+        let num_vertices = model.vertices.len();
+        match model
+            .vertex_ex_info
+            .get_or_insert(VertexExInfo::SubVersion2(Vec::new()))
+        {
+            VertexExInfo::SubVersion1(ref mut vertex_ex) => {
+                let default = VertexEx1 {
+                    bone_ids: [-1; 3],
+                    weights: [0; 3],
+                };
+                vertex_ex.resize(num_vertices, default);
+            }
+            VertexExInfo::SubVersion2(ref mut vertex_ex) => {
+                let default = VertexEx2 {
+                    bone_ids: [-1; 3],
+                    weights: [0; 3],
+                    extra: 0,
+                };
+                vertex_ex.resize(num_vertices, default);
+            }
+            VertexExInfo::SubVersion3(ref mut vertex_ex) => {
+                let default = VertexEx3 {
+                    bone_ids: [-1; 3],
+                    weights: [0; 3],
+                    extra: [0; 2],
+                };
+                vertex_ex.resize(num_vertices, default);
+            }
+        }
+
+        let num_joints = model.joints.len();
+        let joint_ex_info = model.joint_ex_info.get_or_insert(JointExInfo {
+            sub_version: 1,
+            joint_ex: Vec::new(),
+        });
+        let default = JointEx { color: [1.0; 3] };
+        joint_ex_info.joint_ex.resize(num_joints, default);
+
+        if model.model_ex_info.is_none() {
+            model.model_ex_info = Some(ModelExInfo {
+                sub_version: 1,
+                model_ex: ModelEx {
+                    joint_size: 1.0,
+                    transparency_mode: 0,
+                    alpha_ref: 0.5,
+                },
+            });
+        }
+        model
+    }
+
+    fn section_len(&self, section: Section) -> usize {
+        match section {
+            Section::Vertices => self.model.vertices.len(),
+            Section::Triangles => self.model.triangles.len(),
+            Section::Groups => self.model.groups.len(),
+            Section::Materials => self.model.materials.len(),
+            Section::Joints => self.model.joints.len(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn check_index(&self, section: Section, index: usize) -> Result<()> {
+        let len = self.section_len(section);
+        if index >= len {
+            return Err(Error::IndexOutOfRange {
+                section,
+                index,
+                len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check that another element can be added to a section, returning its
+    /// index.
+    fn check_len(&self, section: Section, max: usize) -> Result<usize> {
+        let len = self.section_len(section);
+        if len >= max {
+            return Err(Error::TooManyElements {
+                section,
+                len: len + 1,
+            });
+        }
+        Ok(len)
+    }
+}
+
+impl Default for ModelBuilder {
+    fn default() -> Self {
+        ModelBuilder::new()
+    }
+}
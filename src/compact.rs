@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use super::Model;
+
+/// The elements removed by [`Model::compact`](struct.Model.html#method.compact).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompactReport {
+    pub removed_vertices: usize,
+    pub removed_materials: usize,
+    pub removed_joints: usize,
+}
+
+impl CompactReport {
+    /// Returns true if the model was not changed.
+    pub fn is_empty(&self) -> bool {
+        *self == CompactReport::default()
+    }
+}
+
+impl Model {
+    /// Remove vertices which are not used by any triangle, materials which are
+    /// not used by any group, and joints which no vertex is attached to either
+    /// directly or through one of the joint's descendants.
+    ///
+    /// The elements are removed with the `remove_*` methods, so every index
+    /// into the model is kept consistent.
+    pub fn compact(&mut self) -> CompactReport {
+        let mut used = vec![false; self.vertices.len()];
+        for triangle in &self.triangles {
+            for &index in &triangle.vertex_indices {
+                if let Some(used) = used.get_mut(index as usize) {
+                    *used = true;
+                }
+            }
+        }
+        let vertices = unused(&used);
+        self.remove_vertices(&vertices);
+
+        let mut used = vec![false; self.materials.len()];
+        for group in &self.groups {
+            if let Some(used) = used.get_mut(group.material_index as usize) {
+                *used = true;
+            }
+        }
+        let materials = unused(&used);
+        for &index in materials.iter().rev() {
+            self.remove_material(index);
+        }
+
+        let joints = unused(&self.used_joints());
+        for &index in joints.iter().rev() {
+            self.remove_joint(index);
+        }
+
+        CompactReport {
+            removed_vertices: vertices.len(),
+            removed_materials: materials.len(),
+            removed_joints: joints.len(),
+        }
+    }
+
+    /// Find the joints which have a vertex attached to them or to one of
+    /// their descendants.
+    fn used_joints(&self) -> Vec<bool> {
+        let mut used = vec![false; self.joints.len()];
+        let mut attach = |bone_id: i8| {
+            if let Some(used) = used.get_mut(bone_id as usize) {
+                *used = true;
+            }
+        };
+        let info = self.vertex_ex_info.as_ref();
+        for (index, vertex) in self.vertices.iter().enumerate() {
+            attach(vertex.bone_id);
+            if let Some((bone_ids, _)) = info.and_then(|info| info.bones(index)) {
+                for &bone_id in &bone_ids {
+                    attach(bone_id);
+                }
+            }
+        }
+
+        let mut indices = HashMap::new();
+        for (index, joint) in self.joints.iter().enumerate().rev() {
+            indices.insert(&joint.name[..], index);
+        }
+        for index in 0..self.joints.len() {
+            if !used[index] {
+                continue;
+            }
+            // Mark the ancestors, stopping after as many steps as there are
+            // joints in case the hierarchy has a cycle.
+            let mut parent = &self.joints[index].parent_name;
+            for _ in 0..self.joints.len() {
+                match indices.get(&parent[..]) {
+                    Some(&parent_index) if !used[parent_index] => {
+                        used[parent_index] = true;
+                        parent = &self.joints[parent_index].parent_name;
+                    }
+                    _ => break,
+                }
+            }
+        }
+        used
+    }
+}
+
+/// The indices of the elements not marked in `used`.
+fn unused(used: &[bool]) -> Vec<usize> {
+    used.iter()
+        .enumerate()
+        .filter(|&(_, &used)| !used)
+        .map(|(index, _)| index)
+        .collect()
+}
@@ -1,122 +1,195 @@
-#![allow(dead_code)] // not sure why this is necessary?
-
-#[repr(C, packed)]
-pub(crate) struct Header {
-    pub id: [u8; 10],
-    pub version: i32,
-}
-
-#[repr(C, packed)]
-pub(crate) struct Vertex {
-    pub flags: u8,
-    pub vertex: [f32; 3],
-    pub bone_id: i8,
-    pub reference_count: u8,
-}
-
-#[repr(C, packed)]
-pub(crate) struct Triangle {
-    pub flags: u16,
-    pub vertex_indices: [u16; 3],
-    pub vertex_normals: [[f32; 3]; 3],
-    pub s: [f32; 3],
-    pub t: [f32; 3],
-    pub smoothing_group: u8,
-    pub group_index: u8,
-}
-
-#[repr(C, packed)]
-pub(crate) struct GroupPrefix {
-    pub flags: u8,
-    pub name: [u8; 32],
-    pub num_triangles: u16,
-}
-
-#[repr(C, packed)]
-pub(crate) struct GroupSuffix {
-    pub material_index: i8,
-}
-
-#[repr(C, packed)]
-pub(crate) struct Material {
-    pub name: [u8; 32],
-    pub ambient: [f32; 4],
-    pub diffuse: [f32; 4],
-    pub specular: [f32; 4],
-    pub emissive: [f32; 4],
-    pub shininess: f32,
-    pub transparency: f32,
-    pub mode: u8,
-    pub texture: [u8; 128],
-    pub alphamap: [u8; 128],
-}
-
-#[repr(C, packed)]
-pub(crate) struct KeyFrameData {
-    pub animation_fps: f32,
-    pub current_time: f32,
-    pub total_frames: i32,
-}
-
-#[repr(C, packed)]
-pub(crate) struct KeyFrameRot {
-    pub time: f32,
-    pub rotation: [f32; 3],
-}
-
-#[repr(C, packed)]
-pub(crate) struct KeyFramePos {
-    pub time: f32,
-    pub position: [f32; 3],
-}
-
-#[repr(C, packed)]
-pub(crate) struct JointPrefix {
-    pub flags: u8,
-    pub name: [u8; 32],
-    pub parent_name: [u8; 32],
-    pub rotation: [f32; 3],
-    pub position: [f32; 3],
-    pub num_key_frames_rot: u16,
-    pub num_key_frames_trans: u16,
-}
-
-#[repr(C, packed)]
-pub(crate) struct CommentPrefix {
-    pub index: i32,
-    pub comment_length: i32,
-}
-
-#[repr(C, packed)]
-pub(crate) struct VertexEx1 {
-    pub bone_ids: [i8; 3],
-    pub weights: [u8; 3],
-}
-
-#[repr(C, packed)]
-pub(crate) struct VertexEx2 {
-    pub bone_ids: [i8; 3],
-    pub weights: [u8; 3],
-    pub extra: u32,
-}
-
-#[repr(C, packed)]
-pub(crate) struct VertexEx3 {
-    pub bone_ids: [i8; 3],
-    pub weights: [u8; 3],
-    pub extra: [u32; 2],
-}
-
-#[repr(C, packed)]
-pub(crate) struct JointEx {
-    pub color: [f32; 3],
-}
-
-#[repr(C, packed)]
-pub(crate) struct ModelEx {
-    pub joint_size: f32,
-    pub transparency_mode: i32,
-    pub alpha_ref: f32,
+//! The on-disk layout of the ms3d format. All values are stored in little-endian
+//! byte order, and are decoded field by field so the host byte order and struct
+//! layout do not matter.
+
+use std::{array, mem};
+
+/// A value with a fixed size little-endian encoding.
+pub(crate) trait Le: Sized {
+    /// The size of the encoded value in bytes.
+    const SIZE: usize;
+
+    /// Decode a value from the first `SIZE` bytes of `bytes`.
+    fn read_le(bytes: &[u8]) -> Self;
+
+    /// Append the encoded value to `buf`.
+    fn write_le(&self, buf: &mut Vec<u8>);
+}
+
+macro_rules! le_primitive {
+    ($($ty:ty),*) => {$(
+        impl Le for $ty {
+            const SIZE: usize = mem::size_of::<$ty>();
+
+            fn read_le(bytes: &[u8]) -> Self {
+                let mut array = [0; mem::size_of::<$ty>()];
+                array.copy_from_slice(&bytes[..Self::SIZE]);
+                <$ty>::from_le_bytes(array)
+            }
+
+            fn write_le(&self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    )*};
+}
+
+le_primitive!(u8, i8, u16, i32, u32, f32);
+
+impl<T: Le, const N: usize> Le for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn read_le(bytes: &[u8]) -> Self {
+        array::from_fn(|i| T::read_le(&bytes[i * T::SIZE..]))
+    }
+
+    fn write_le(&self, buf: &mut Vec<u8>) {
+        for value in self {
+            value.write_le(buf);
+        }
+    }
+}
+
+/// Declare a struct in the on-disk format, and implement `Le` for it by
+/// encoding each field in order with no padding.
+macro_rules! le_struct {
+    ($(
+        pub(crate) struct $name:ident {
+            $(pub $field:ident: $ty:ty,)*
+        }
+    )*) => {$(
+        #[derive(Copy, Clone, Debug)]
+        #[repr(C, packed)]
+        pub(crate) struct $name {
+            $(pub $field: $ty,)*
+        }
+
+        impl Le for $name {
+            const SIZE: usize = 0 $(+ <$ty as Le>::SIZE)*;
+
+            #[allow(unused_assignments)]
+            fn read_le(bytes: &[u8]) -> Self {
+                let mut offset = 0;
+                $(
+                    let $field = <$ty as Le>::read_le(&bytes[offset..]);
+                    offset += <$ty as Le>::SIZE;
+                )*
+                $name { $($field,)* }
+            }
+
+            fn write_le(&self, buf: &mut Vec<u8>) {
+                $({
+                    let $field = self.$field;
+                    $field.write_le(buf);
+                })*
+            }
+        }
+    )*};
+}
+
+le_struct! {
+    pub(crate) struct Header {
+        pub id: [u8; 10],
+        pub version: i32,
+    }
+
+    pub(crate) struct Vertex {
+        pub flags: u8,
+        pub vertex: [f32; 3],
+        pub bone_id: i8,
+        pub reference_count: u8,
+    }
+
+    pub(crate) struct Triangle {
+        pub flags: u16,
+        pub vertex_indices: [u16; 3],
+        pub vertex_normals: [[f32; 3]; 3],
+        pub s: [f32; 3],
+        pub t: [f32; 3],
+        pub smoothing_group: u8,
+        pub group_index: u8,
+    }
+
+    pub(crate) struct GroupPrefix {
+        pub flags: u8,
+        pub name: [u8; 32],
+        pub num_triangles: u16,
+    }
+
+    pub(crate) struct GroupSuffix {
+        pub material_index: i8,
+    }
+
+    pub(crate) struct Material {
+        pub name: [u8; 32],
+        pub ambient: [f32; 4],
+        pub diffuse: [f32; 4],
+        pub specular: [f32; 4],
+        pub emissive: [f32; 4],
+        pub shininess: f32,
+        pub transparency: f32,
+        pub mode: u8,
+        pub texture: [u8; 128],
+        pub alphamap: [u8; 128],
+    }
+
+    pub(crate) struct KeyFrameData {
+        pub animation_fps: f32,
+        pub current_time: f32,
+        pub total_frames: i32,
+    }
+
+    pub(crate) struct KeyFrameRot {
+        pub time: f32,
+        pub rotation: [f32; 3],
+    }
+
+    pub(crate) struct KeyFramePos {
+        pub time: f32,
+        pub position: [f32; 3],
+    }
+
+    pub(crate) struct JointPrefix {
+        pub flags: u8,
+        pub name: [u8; 32],
+        pub parent_name: [u8; 32],
+        pub rotation: [f32; 3],
+        pub position: [f32; 3],
+        pub num_key_frames_rot: u16,
+        pub num_key_frames_trans: u16,
+    }
+
+    pub(crate) struct CommentPrefix {
+        pub index: i32,
+        pub comment_length: i32,
+    }
+
+    pub(crate) struct VertexEx1 {
+        pub bone_ids: [i8; 3],
+        pub weights: [u8; 3],
+    }
+
+    pub(crate) struct VertexEx2 {
+        pub bone_ids: [i8; 3],
+        pub weights: [u8; 3],
+        pub extra: u32,
+    }
+
+    pub(crate) struct VertexEx3 {
+        pub bone_ids: [i8; 3],
+        pub weights: [u8; 3],
+        pub extra: [u32; 2],
+    }
+
+    pub(crate) struct JointEx {
+        pub color: [f32; 3],
+    }
+
+    pub(crate) struct ModelEx {
+        pub joint_size: f32,
+        pub transparency_mode: i32,
+        pub alpha_ref: f32,
+    }
 }
 
 #[test]
@@ -129,4 +202,11 @@ fn test_sizes() {
     assert_eq!(size_of::<Material>(), 361);
     assert_eq!(size_of::<KeyFrameRot>(), 16);
     assert_eq!(size_of::<KeyFramePos>(), 16);
+
+    assert_eq!(Header::SIZE, 14);
+    assert_eq!(Vertex::SIZE, 15);
+    assert_eq!(Triangle::SIZE, 70);
+    assert_eq!(Material::SIZE, 361);
+    assert_eq!(KeyFrameRot::SIZE, 16);
+    assert_eq!(KeyFramePos::SIZE, 16);
 }
@@ -0,0 +1,57 @@
+use std::fmt;
+
+use super::Section;
+
+/// An oddity in a model file which is not worth failing over, collected while
+/// parsing by
+/// [`Model::from_reader_diagnostic`](struct.Model.html#method.from_reader_diagnostic).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub section: Section,
+    pub index: usize,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiagnosticKind {
+    /// A joint has key frames, but the total number of frames is 0.
+    NoFrames,
+    /// A joint has a key frame outside the length of the animation, which is
+    /// `total_frames / animation_fps` seconds.
+    KeyFrameOutOfRange { time: f32 },
+    /// A group has an empty name.
+    EmptyGroupName,
+    /// The reference count of a vertex is not the number of triangle corners
+    /// which use it.
+    ReferenceCountMismatch { reference_count: u8, actual: u8 },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DiagnosticKind::NoFrames => write!(
+                f,
+                "element {} of {} has key frames, but total frames is 0",
+                self.index, self.section
+            ),
+            DiagnosticKind::KeyFrameOutOfRange { time } => write!(
+                f,
+                "element {} of {} has a key frame at time {} outside the animation",
+                self.index, self.section, time
+            ),
+            DiagnosticKind::EmptyGroupName => write!(
+                f,
+                "element {} of {} has an empty name",
+                self.index, self.section
+            ),
+            DiagnosticKind::ReferenceCountMismatch {
+                reference_count,
+                actual,
+            } => write!(
+                f,
+                "element {} of {} has reference count {}, but is used {} times",
+                self.index, self.section, reference_count, actual
+            ),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use super::model::*;
+
+/// Build a map from old to new indices for a list with the elements marked in
+/// `keep` retained.
+pub(crate) fn index_map(keep: &[bool]) -> Vec<Option<usize>> {
+    let mut next = 0;
+    keep.iter()
+        .map(|&keep| {
+            if keep {
+                next += 1;
+                Some(next - 1)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Remove the elements of `vec` which are not marked in `keep`. Elements past
+/// the end of `keep` are retained.
+pub(crate) fn retain_marked<T>(vec: &mut Vec<T>, keep: &[bool]) {
+    let mut keep = keep.iter();
+    vec.retain(|_| *keep.next().unwrap_or(&true));
+}
+
+/// Remove the comments on removed elements and update the indices of the
+/// rest.
+pub(crate) fn remap_comments(comments: &mut Vec<Comment>, map: &[Option<usize>]) {
+    comments.retain(|comment| comment.index < 0 || map.get(comment.index as usize) != Some(&None));
+    for comment in comments {
+        if let Some(&Some(index)) = map.get(comment.index as usize) {
+            comment.index = index as i32;
+        }
+    }
+}
+
+/// Update an index where -1 means none, setting it to -1 if the element it
+/// refers to was removed.
+fn remap_optional(index: &mut i8, map: &[Option<usize>]) {
+    if *index < 0 {
+        return;
+    }
+    match map.get(*index as usize) {
+        Some(&Some(new)) => *index = new as i8,
+        Some(&None) => *index = -1,
+        None => {}
+    }
+}
+
+/// Mark the elements at `indices` for removal, returning which elements of a
+/// list of `len` elements to keep.
+fn removal_mask(len: usize, indices: &[usize]) -> Vec<bool> {
+    let mut keep = vec![true; len];
+    for &index in indices {
+        assert!(
+            index < len,
+            "index {} out of range for {} elements",
+            index,
+            len
+        );
+        keep[index] = false;
+    }
+    keep
+}
+
+impl VertexExInfo {
+    fn retain_marked(&mut self, keep: &[bool]) {
+        match *self {
+            VertexExInfo::SubVersion1(ref mut vertex_ex) => retain_marked(vertex_ex, keep),
+            VertexExInfo::SubVersion2(ref mut vertex_ex) => retain_marked(vertex_ex, keep),
+            VertexExInfo::SubVersion3(ref mut vertex_ex) => retain_marked(vertex_ex, keep),
+        }
+    }
+}
+
+/// Editing which keeps the references between elements consistent.
+///
+/// Removing an element updates every index which refers to the elements after
+/// it, and any element which cannot exist without it is removed as well.
+/// Indices which were already out of range are left unchanged.
+impl Model {
+    /// Remove vertices, along with every triangle which uses one of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of range.
+    pub fn remove_vertices(&mut self, indices: &[usize]) {
+        let keep = removal_mask(self.vertices.len(), indices);
+        let triangles: Vec<usize> = self
+            .triangles
+            .iter()
+            .enumerate()
+            .filter(|&(_, triangle)| {
+                triangle
+                    .vertex_indices
+                    .iter()
+                    .any(|&index| keep.get(index as usize) == Some(&false))
+            })
+            .map(|(index, _)| index)
+            .collect();
+        self.remove_triangles(&triangles);
+
+        let map = index_map(&keep);
+        retain_marked(&mut self.vertices, &keep);
+        if let Some(ref mut info) = self.vertex_ex_info {
+            info.retain_marked(&keep);
+        }
+        for triangle in &mut self.triangles {
+            for index in &mut triangle.vertex_indices {
+                if let Some(&Some(new)) = map.get(*index as usize) {
+                    *index = new as u16;
+                }
+            }
+        }
+    }
+
+    /// Remove triangles, taking them out of their groups and decrementing the
+    /// reference counts of their vertices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of range.
+    pub fn remove_triangles(&mut self, indices: &[usize]) {
+        let keep = removal_mask(self.triangles.len(), indices);
+        for (triangle, _) in self.triangles.iter().zip(&keep).filter(|&(_, &keep)| !keep) {
+            for &index in &triangle.vertex_indices {
+                if let Some(vertex) = self.vertices.get_mut(index as usize) {
+                    vertex.reference_count = vertex.reference_count.saturating_sub(1);
+                }
+            }
+        }
+
+        let map = index_map(&keep);
+        retain_marked(&mut self.triangles, &keep);
+        for group in &mut self.groups {
+            group
+                .triangle_indices
+                .retain(|&index| map.get(index as usize) != Some(&None));
+            for index in &mut group.triangle_indices {
+                if let Some(&Some(new)) = map.get(*index as usize) {
+                    *index = new as u16;
+                }
+            }
+        }
+    }
+
+    /// Remove a group, along with its triangles and its comment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of range.
+    pub fn remove_group(&mut self, index: usize) {
+        let keep = removal_mask(self.groups.len(), &[index]);
+        let num_triangles = self.triangles.len();
+        let mut triangles: Vec<usize> = self.groups[index]
+            .triangle_indices
+            .iter()
+            .map(|&index| index as usize)
+            .filter(|&index| index < num_triangles)
+            .collect();
+        for (triangle_index, triangle) in self.triangles.iter().enumerate() {
+            if triangle.group_index as usize == index {
+                triangles.push(triangle_index);
+            }
+        }
+        self.remove_triangles(&triangles);
+
+        let map = index_map(&keep);
+        self.groups.remove(index);
+        for triangle in &mut self.triangles {
+            if let Some(&Some(new)) = map.get(triangle.group_index as usize) {
+                triangle.group_index = new as u8;
+            }
+        }
+        if let Some(ref mut comments) = self.comments {
+            remap_comments(&mut comments.group_comments, &map);
+        }
+    }
+
+    /// Remove a material and its comment. Groups which used it are left with
+    /// no material.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of range.
+    pub fn remove_material(&mut self, index: usize) {
+        let map = index_map(&removal_mask(self.materials.len(), &[index]));
+        self.materials.remove(index);
+        for group in &mut self.groups {
+            remap_optional(&mut group.material_index, &map);
+        }
+        if let Some(ref mut comments) = self.comments {
+            remap_comments(&mut comments.material_comments, &map);
+        }
+    }
+
+    /// Remove a joint, its extra info and its comment. Vertices attached to it
+    /// are left with no joint.
+    ///
+    /// The joint's children are given its parent, so their `parent_name` and
+    /// `raw_parent_name` change to the removed joint's parent name. Children
+    /// of a joint with no parent become joints with no parent. This keeps
+    /// every parent name referring to a joint which exists.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of range.
+    pub fn remove_joint(&mut self, index: usize) {
+        let map = index_map(&removal_mask(self.joints.len(), &[index]));
+        let joint = self.joints.remove(index);
+        for child in &mut self.joints {
+            if child.parent_name == joint.name {
+                child.parent_name = joint.parent_name.clone();
+                child.raw_parent_name = joint.raw_parent_name.clone();
+            }
+        }
+
+        for vertex in &mut self.vertices {
+            remap_optional(&mut vertex.bone_id, &map);
+        }
+        if let Some(ref mut info) = self.vertex_ex_info {
+            for vertex in 0..info.len() {
+                let (bone_ids, _) = info.bones_mut(vertex).unwrap();
+                for bone_id in bone_ids.iter_mut() {
+                    remap_optional(bone_id, &map);
+                }
+            }
+        }
+        if let Some(ref mut info) = self.joint_ex_info {
+            if index < info.joint_ex.len() {
+                info.joint_ex.remove(index);
+            }
+        }
+        if let Some(ref mut comments) = self.comments {
+            remap_comments(&mut comments.joint_comments, &map);
+        }
+    }
+}
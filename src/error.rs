@@ -0,0 +1,313 @@
+use std::error::Error as StdError;
+use std::path::PathBuf;
+use std::{fmt, io};
+
+/// A section of an ms3d model file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Section {
+    Header,
+    Vertices,
+    Triangles,
+    Groups,
+    Materials,
+    KeyFrameData,
+    Joints,
+    Comments,
+    VertexExInfo,
+    JointExInfo,
+    ModelExInfo,
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Section::Header => "header",
+            Section::Vertices => "vertices",
+            Section::Triangles => "triangles",
+            Section::Groups => "groups",
+            Section::Materials => "materials",
+            Section::KeyFrameData => "key frame data",
+            Section::Joints => "joints",
+            Section::Comments => "comments",
+            Section::VertexExInfo => "vertex extra info",
+            Section::JointExInfo => "joint extra info",
+            Section::ModelExInfo => "model extra info",
+        })
+    }
+}
+
+/// A limit on the size of a model file, set in
+/// [`Limits`](struct.Limits.html).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Limit {
+    /// The total number of comments.
+    Comments,
+    /// The length of a single comment.
+    CommentLength,
+    /// The total number of bytes read.
+    TotalBytes,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Limit::Comments => "number of comments",
+            Limit::CommentLength => "comment length",
+            Limit::TotalBytes => "total size",
+        })
+    }
+}
+
+/// An error encountered while reading or writing an ms3d model.
+///
+/// Errors encountered while reading carry the byte offset into the input at
+/// which the problem was found.
+#[derive(Debug)]
+pub enum Error {
+    /// The file does not start with the `MS3D000000` identifier.
+    BadMagic { offset: u64 },
+    /// The file format version is not supported.
+    UnsupportedVersion { version: i32, offset: u64 },
+    /// The sub-version of one of the optional sections is not supported.
+    UnsupportedSubVersion {
+        section: Section,
+        sub_version: i32,
+        offset: u64,
+    },
+    /// An element has flags which are not valid for its type.
+    InvalidFlags {
+        section: Section,
+        index: usize,
+        bits: u16,
+        offset: u64,
+    },
+    /// A name, path or comment is not valid in the text encoding used.
+    InvalidText {
+        section: Section,
+        index: usize,
+        offset: u64,
+    },
+    /// An element count is out of range.
+    InvalidCount {
+        section: Section,
+        count: i64,
+        offset: u64,
+    },
+    /// The input ended in the middle of a section.
+    UnexpectedEof { section: Section, offset: u64 },
+    /// The file exceeds one of the configured limits.
+    LimitExceeded {
+        section: Section,
+        limit: Limit,
+        offset: u64,
+    },
+    /// An IO error occurred.
+    Io { offset: u64, error: io::Error },
+    /// An error occurred while reading the file at `path`. The message only
+    /// names the file, and `error` is returned as the source.
+    File { path: PathBuf, error: Box<Error> },
+    /// A string is too long to fit in its fixed size field.
+    StringTooLong {
+        section: Section,
+        index: usize,
+        len: usize,
+        max: usize,
+    },
+    /// A path is not valid unicode.
+    InvalidPath { section: Section, index: usize },
+    /// A name, path or comment cannot be represented in the text encoding used.
+    UnencodableText { section: Section, index: usize },
+    /// A section has more elements than can be stored in the file.
+    TooManyElements { section: Section, len: usize },
+    /// A section does not have one element for each element of the section it
+    /// extends.
+    LengthMismatch {
+        section: Section,
+        expected: usize,
+        found: usize,
+    },
+    /// An optional section is missing, but a later one is present or it is
+    /// needed to patch the file.
+    MissingSection { section: Section },
+    /// An element index is past the end of its section.
+    IndexOutOfRange {
+        section: Section,
+        index: usize,
+        len: usize,
+    },
+    /// An optional section is set on a model whose format version is not
+    /// written with optional sections.
+    UnsupportedSection { section: Section, version: i32 },
+}
+
+impl Error {
+    /// The byte offset at which a read error occurred.
+    pub fn offset(&self) -> Option<u64> {
+        match *self {
+            Error::File { ref error, .. } => error.offset(),
+            Error::BadMagic { offset }
+            | Error::UnsupportedVersion { offset, .. }
+            | Error::UnsupportedSubVersion { offset, .. }
+            | Error::InvalidFlags { offset, .. }
+            | Error::InvalidText { offset, .. }
+            | Error::InvalidCount { offset, .. }
+            | Error::UnexpectedEof { offset, .. }
+            | Error::LimitExceeded { offset, .. }
+            | Error::Io { offset, .. } => Some(offset),
+            Error::StringTooLong { .. }
+            | Error::InvalidPath { .. }
+            | Error::UnencodableText { .. }
+            | Error::TooManyElements { .. }
+            | Error::LengthMismatch { .. }
+            | Error::MissingSection { .. }
+            | Error::IndexOutOfRange { .. }
+            | Error::UnsupportedSection { .. } => None,
+        }
+    }
+
+    /// The section of the file in which the error occurred, if known.
+    pub fn section(&self) -> Option<Section> {
+        match *self {
+            Error::File { ref error, .. } => error.section(),
+            Error::BadMagic { .. } | Error::UnsupportedVersion { .. } => Some(Section::Header),
+            Error::UnsupportedSubVersion { section, .. }
+            | Error::InvalidFlags { section, .. }
+            | Error::InvalidText { section, .. }
+            | Error::InvalidCount { section, .. }
+            | Error::UnexpectedEof { section, .. }
+            | Error::LimitExceeded { section, .. }
+            | Error::StringTooLong { section, .. }
+            | Error::InvalidPath { section, .. }
+            | Error::UnencodableText { section, .. }
+            | Error::TooManyElements { section, .. }
+            | Error::LengthMismatch { section, .. }
+            | Error::MissingSection { section }
+            | Error::IndexOutOfRange { section, .. }
+            | Error::UnsupportedSection { section, .. } => Some(section),
+            Error::Io { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::BadMagic { offset } => write!(f, "invalid header at offset {}", offset),
+            Error::UnsupportedVersion { version, offset } => {
+                write!(f, "unsupported version {} at offset {}", version, offset)
+            }
+            Error::UnsupportedSubVersion {
+                section,
+                sub_version,
+                offset,
+            } => write!(
+                f,
+                "unsupported {} sub-version {} at offset {}",
+                section, sub_version, offset
+            ),
+            Error::InvalidFlags {
+                section,
+                index,
+                bits,
+                offset,
+            } => write!(
+                f,
+                "invalid flags {} for element {} of {} at offset {}",
+                bits, index, section, offset
+            ),
+            Error::InvalidText {
+                section,
+                index,
+                offset,
+            } => write!(
+                f,
+                "invalid text in element {} of {} at offset {}",
+                index, section, offset
+            ),
+            Error::InvalidCount {
+                section,
+                count,
+                offset,
+            } => write!(
+                f,
+                "invalid number of {} ({}) at offset {}",
+                section, count, offset
+            ),
+            Error::UnexpectedEof { section, offset } => write!(
+                f,
+                "unexpected end of file in {} at offset {}",
+                section, offset
+            ),
+            Error::LimitExceeded {
+                section,
+                limit,
+                offset,
+            } => write!(
+                f,
+                "{} limit exceeded in {} at offset {}",
+                limit, section, offset
+            ),
+            Error::Io { offset, ref error } => write!(f, "{} at offset {}", error, offset),
+            Error::File { ref path, .. } => write!(f, "error reading {}", path.display()),
+            Error::StringTooLong {
+                section,
+                index,
+                len,
+                max,
+            } => write!(
+                f,
+                "string of {} bytes in element {} of {} is longer than {} bytes",
+                len, index, section, max
+            ),
+            Error::InvalidPath { section, index } => write!(
+                f,
+                "path in element {} of {} is not valid unicode",
+                index, section
+            ),
+            Error::UnencodableText { section, index } => write!(
+                f,
+                "text in element {} of {} cannot be encoded",
+                index, section
+            ),
+            Error::TooManyElements { section, len } => {
+                write!(f, "too many elements in {} ({})", section, len)
+            }
+            Error::LengthMismatch {
+                section,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected {} elements in {}, found {}",
+                expected, section, found
+            ),
+            Error::MissingSection { section } => write!(f, "{} section is missing", section),
+            Error::IndexOutOfRange {
+                section,
+                index,
+                len,
+            } => write!(
+                f,
+                "element {} of {} is out of range, the section has {} elements",
+                index, section, len
+            ),
+            Error::UnsupportedSection { section, version } => {
+                write!(
+                    f,
+                    "{} cannot be written in a version {} file",
+                    section, version
+                )
+            }
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            Error::Io { ref error, .. } => Some(error),
+            Error::File { ref error, .. } => Some(&**error),
+            _ => None,
+        }
+    }
+}
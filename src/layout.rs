@@ -0,0 +1,257 @@
+use std::io;
+use std::ops::Range;
+
+use super::de::{self, Le};
+use super::read::{BufReadExact, IoReader, SeekReader};
+use super::{Error, ParseOptions, Reader, Result, Section, GROUP_MIN_SIZE};
+
+/// The byte ranges of the sections and records of a model file, returned by
+/// [`layout`](fn.layout.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileLayout {
+    pub header: Range<u64>,
+    pub vertices: SectionLayout,
+    pub triangles: SectionLayout,
+    pub groups: SectionLayout,
+    pub materials: SectionLayout,
+    pub key_frame_data: Range<u64>,
+    pub joints: SectionLayout<JointLayout>,
+    pub comments: Option<CommentsLayout>,
+    pub vertex_ex_info: Option<SectionLayout>,
+    pub joint_ex_info: Option<SectionLayout>,
+    pub model_ex_info: Option<Range<u64>>,
+    /// Any data after the last section.
+    pub unknown_trailer: Range<u64>,
+}
+
+/// The layout of a section made up of a list of records.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SectionLayout<T = Range<u64>> {
+    /// The whole section, including its length and sub-version.
+    pub range: Range<u64>,
+    pub records: Vec<T>,
+}
+
+/// The layout of a joint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JointLayout {
+    /// The whole joint, including its key frames.
+    pub range: Range<u64>,
+    pub key_frames_rot: Range<u64>,
+    pub key_frames_trans: Range<u64>,
+}
+
+/// The layout of the comments section.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommentsLayout {
+    pub range: Range<u64>,
+    pub group_comments: Vec<Range<u64>>,
+    pub material_comments: Vec<Range<u64>>,
+    pub joint_comments: Vec<Range<u64>>,
+    pub model_comment: Option<Range<u64>>,
+}
+
+/// Find the byte range of every section and record of an ms3d model file.
+///
+/// The file is walked the same way as
+/// [`Model::from_reader`](struct.Model.html#method.from_reader), but only the
+/// lengths needed to find each record are decoded.
+pub fn layout<R: io::Read>(rdr: R) -> Result<FileLayout> {
+    Reader::new(IoReader::new(rdr), ParseOptions::default()).read_layout()
+}
+
+/// Find the byte range of every section and record of an ms3d model file in a
+/// seekable reader.
+///
+/// This is the same as [`layout`](fn.layout.html), but records are skipped by
+/// seeking past them instead of reading them.
+pub fn layout_seek<R: io::Read + io::Seek>(rdr: R) -> Result<FileLayout> {
+    let rdr = SeekReader::new(rdr).map_err(|error| Error::Io { offset: 0, error })?;
+    Reader::new(rdr, ParseOptions::default()).read_layout()
+}
+
+impl<R: BufReadExact> Reader<R> {
+    fn read_layout(&mut self) -> Result<FileLayout> {
+        let start = self.rdr.position();
+        self.read_header()?;
+        let header = start..self.rdr.position();
+
+        self.begin_section(Section::Vertices);
+        let vertices = self.fixed_size_layout(de::Vertex::SIZE)?;
+        self.begin_section(Section::Triangles);
+        let triangles = self.fixed_size_layout(de::Triangle::SIZE)?;
+
+        self.begin_section(Section::Groups);
+        let groups = self.section_layout(GROUP_MIN_SIZE, |this| {
+            let prefix = this.read_type::<de::GroupPrefix>()?;
+            this.skip_bytes(prefix.num_triangles as usize * 2)?;
+            this.skip_bytes(de::GroupSuffix::SIZE)
+        })?;
+
+        self.begin_section(Section::Materials);
+        let materials = self.fixed_size_layout(de::Material::SIZE)?;
+
+        let start = self.rdr.position();
+        self.read_key_frame_data()?;
+        let key_frame_data = start..self.rdr.position();
+
+        self.begin_section(Section::Joints);
+        let joints = self.read_layout_joints()?;
+
+        let comments = self.read_optional(Self::read_layout_comments)?;
+        let num_vertices = vertices.records.len();
+        let vertex_ex_info =
+            self.read_optional(|this| this.read_layout_vertex_ex_info(num_vertices))?;
+        let num_joints = joints.records.len();
+        let joint_ex_info = self.read_optional(|this| {
+            let start = this.rdr.position();
+            this.begin_section(Section::JointExInfo);
+            if this.read_sub_version(&[1])?.is_none() {
+                return Ok(None);
+            }
+            this.fixed_size_records(start, num_joints, de::JointEx::SIZE)
+                .map(Some)
+        })?;
+        let model_ex_info = self.read_optional(|this| {
+            let start = this.rdr.position();
+            this.read_model_ex_info()?;
+            Ok(Some(start..this.rdr.position()))
+        })?;
+
+        let start = self.rdr.position();
+        self.read_trailer()?;
+        let unknown_trailer = start..self.rdr.position();
+
+        Ok(FileLayout {
+            header,
+            vertices,
+            triangles,
+            groups,
+            materials,
+            key_frame_data,
+            joints,
+            comments,
+            vertex_ex_info,
+            joint_ex_info,
+            model_ex_info,
+            unknown_trailer,
+        })
+    }
+
+    /// Read the layout of a section with a length followed by records of at
+    /// least `size` bytes.
+    fn section_layout<F>(&mut self, size: usize, mut f: F) -> Result<SectionLayout>
+    where
+        F: FnMut(&mut Self) -> Result<()>,
+    {
+        let start = self.rdr.position();
+        let len = self.read_u16()? as usize;
+        let mut records = Vec::new();
+        self.for_each_element(len, size, |this| {
+            let start = this.rdr.position();
+            f(this)?;
+            records.push(start..this.rdr.position());
+            Ok(())
+        })?;
+        Ok(SectionLayout {
+            range: start..self.rdr.position(),
+            records,
+        })
+    }
+
+    /// Read the layout of a section with a length followed by records of
+    /// `size` bytes.
+    fn fixed_size_layout(&mut self, size: usize) -> Result<SectionLayout> {
+        let start = self.rdr.position();
+        let len = self.read_u16()? as usize;
+        self.fixed_size_records(start, len, size)
+    }
+
+    /// Skip `len` records of `size` bytes, returning the layout of the section
+    /// which started at `start`.
+    fn fixed_size_records(&mut self, start: u64, len: usize, size: usize) -> Result<SectionLayout> {
+        let offset = self.rdr.position();
+        self.skip_bytes(len * size)?;
+        let size = size as u64;
+        let records = (0..len as u64)
+            .map(|index| offset + index * size..offset + (index + 1) * size)
+            .collect();
+        Ok(SectionLayout {
+            range: start..self.rdr.position(),
+            records,
+        })
+    }
+
+    fn read_layout_joints(&mut self) -> Result<SectionLayout<JointLayout>> {
+        let start = self.rdr.position();
+        let len = self.read_u16()? as usize;
+        let mut records = Vec::new();
+        self.for_each_element(len, de::JointPrefix::SIZE, |this| {
+            let start = this.rdr.position();
+            let prefix = this.read_type::<de::JointPrefix>()?;
+            let rot_start = this.rdr.position();
+            this.skip_bytes(prefix.num_key_frames_rot as usize * de::KeyFrameRot::SIZE)?;
+            let trans_start = this.rdr.position();
+            this.skip_bytes(prefix.num_key_frames_trans as usize * de::KeyFramePos::SIZE)?;
+            let end = this.rdr.position();
+            records.push(JointLayout {
+                range: start..end,
+                key_frames_rot: rot_start..trans_start,
+                key_frames_trans: trans_start..end,
+            });
+            Ok(())
+        })?;
+        Ok(SectionLayout {
+            range: start..self.rdr.position(),
+            records,
+        })
+    }
+
+    fn read_layout_comments(&mut self) -> Result<Option<CommentsLayout>> {
+        let start = self.rdr.position();
+        self.begin_section(Section::Comments);
+        if self.read_sub_version(&[1])?.is_none() {
+            return Ok(None);
+        }
+        let mut total = 0;
+        let len = self.read_comment_count(&mut total)?;
+        let group_comments = self.read_layout_comment_list(len)?;
+        let len = self.read_comment_count(&mut total)?;
+        let material_comments = self.read_layout_comment_list(len)?;
+        let len = self.read_comment_count(&mut total)?;
+        let joint_comments = self.read_layout_comment_list(len)?;
+        let len = self.read_model_comment_count(&mut total)?;
+        let model_comment = self.read_layout_comment_list(len)?.pop();
+
+        Ok(Some(CommentsLayout {
+            range: start..self.rdr.position(),
+            group_comments,
+            material_comments,
+            joint_comments,
+            model_comment,
+        }))
+    }
+
+    fn read_layout_comment_list(&mut self, len: usize) -> Result<Vec<Range<u64>>> {
+        let mut records = Vec::new();
+        self.for_each_element(len, de::CommentPrefix::SIZE, |this| {
+            let start = this.rdr.position();
+            this.read_comment()?;
+            records.push(start..this.rdr.position());
+            Ok(())
+        })?;
+        Ok(records)
+    }
+
+    fn read_layout_vertex_ex_info(&mut self, len: usize) -> Result<Option<SectionLayout>> {
+        let start = self.rdr.position();
+        self.begin_section(Section::VertexExInfo);
+        let size = match self.read_sub_version(&[1, 2, 3])? {
+            Some(1) => de::VertexEx1::SIZE,
+            Some(2) => de::VertexEx2::SIZE,
+            Some(_) => de::VertexEx3::SIZE,
+            None => return Ok(None),
+        };
+        self.fixed_size_records(start, len, size).map(Some)
+    }
+}
@@ -1,85 +1,199 @@
 //! This crate provides utilities working with ms3d models. The main entry
 //! point for this crate is the [`Model::from_reader`](struct.Model.html#method.from_reader)
-//! function which parses a model file.
+//! function which parses a model file. Models can be written back out with
+//! [`Model::write_to`](struct.Model.html#method.write_to). For read-only
+//! access to a model in memory without decoding it up front, see
+//! [`ModelRef`](struct.ModelRef.html).
 
 #[macro_use]
 extern crate bitflags;
-#[macro_use]
-extern crate failure;
+#[cfg(feature = "encoding_rs")]
+extern crate encoding_rs;
 extern crate memchr;
-
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+
+#[cfg(feature = "tokio")]
+mod async_read;
+mod builder;
+mod compact;
 mod de;
+mod diagnostic;
+mod edit;
+mod error;
+mod layout;
 mod model;
+mod model_ref;
+mod normals;
+mod options;
+mod patch;
+mod probe;
 mod read;
-
+mod render;
+mod repair;
+mod text;
+mod validate;
+mod visit;
+mod write;
+
+pub use builder::ModelBuilder;
+pub use compact::CompactReport;
+pub use diagnostic::{Diagnostic, DiagnosticKind};
+pub use error::{Error, Limit, Section};
+pub use layout::{layout, layout_seek, CommentsLayout, FileLayout, JointLayout, SectionLayout};
 pub use model::*;
-pub use failure::Error;
-
+pub use model_ref::{
+    CommentIter, CommentRef, CommentsRef, GroupRef, Groups, JointRef, Joints, MaterialRef,
+    ModelRef, Records, VertexExInfoRef,
+};
+pub use normals::{NormalOptions, NormalWeighting};
+pub use options::{Limits, ParseOptions, Warning, WarningKind, WriteOptions};
+pub use patch::Patcher;
+pub use probe::{probe, probe_seek, ModelSummary};
+pub use render::{Indices, RenderMesh, RenderVertex};
+pub use repair::{RepairOptions, RepairReport};
+#[cfg(feature = "encoding_rs")]
+pub use text::{Gbk, ShiftJis};
+pub use text::{TextEncoding, Utf8, Windows1252};
+pub use validate::{IssueKind, ValidationIssue};
+pub use visit::{visit, ModelVisitor};
+
+use de::Le;
 use read::{BufReadExact, IoReader, SliceReader};
 
 use memchr::memchr;
 
 use std::io;
-use std::{mem, ptr, str, u8};
 use std::path::PathBuf;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The minimum size of a group, with no triangles.
+const GROUP_MIN_SIZE: usize = de::GroupPrefix::SIZE + de::GroupSuffix::SIZE;
+
 struct Reader<R: BufReadExact> {
     rdr: R,
+    options: ParseOptions,
+    warnings: Vec<Warning>,
+    diagnostics: Vec<Diagnostic>,
+    /// The key frame data, once it has been read, for checking the key frames
+    /// of each joint.
+    key_frame_data: Option<KeyFrameData>,
+    /// The format version from the header.
+    version: i32,
+    /// The sub-version of an optional section skipped in lenient mode, after
+    /// which the position of any later sections is unknown.
+    skipped: Option<i32>,
+    section: Section,
+    index: usize,
+    /// The offset of the start of the current element.
+    offset: u64,
 }
 
 impl<R: io::Read> Reader<IoReader<R>> {
-    fn from_io_reader(rdr: R) -> Self {
-        Reader { rdr: IoReader::new(rdr) }
+    fn from_io_reader(rdr: R, options: ParseOptions) -> Self {
+        Reader::new(IoReader::new(rdr), options)
     }
 }
 
 impl<'a> Reader<SliceReader<'a>> {
-    fn from_slice(slice: &'a [u8]) -> Self {
-        Reader { rdr: SliceReader::new(slice) }
+    fn from_slice(slice: &'a [u8], options: ParseOptions) -> Self {
+        Reader::new(SliceReader::new(slice), options)
     }
 }
 
 impl<R: BufReadExact> Reader<R> {
+    fn new(rdr: R, options: ParseOptions) -> Self {
+        Reader {
+            rdr,
+            options,
+            warnings: Vec::new(),
+            diagnostics: Vec::new(),
+            key_frame_data: None,
+            version: 4,
+            skipped: None,
+            section: Section::Header,
+            index: 0,
+            offset: 0,
+        }
+    }
+
+    fn read_model_with_warnings(mut self) -> Result<(Model, Vec<Warning>)> {
+        let model = self.read_model()?;
+        Ok((model, self.warnings))
+    }
+
+    fn read_model_with_diagnostics(mut self) -> Result<(Model, Vec<Warning>, Vec<Diagnostic>)> {
+        let model = self.read_model()?;
+        Ok((model, self.warnings, self.diagnostics))
+    }
+
     fn read_model(&mut self) -> Result<Model> {
-        let header = self.read_header()?;
-        let vertices = self.read_vertices()?;
-        let triangles = self.read_triangles()?;
-        let groups = self.read_groups()?;
-        let materials = self.read_materials()?;
-        let key_frame_data = self.read_key_frame_data()?;
-        let joints = self.read_joints()?;
-        let comments = self.read_comments()?;
-        let vertex_ex_info = self.read_vertex_ex_info(vertices.len())?;
-        let joint_ex_info = self.read_joint_ex_info(joints.len())?;
-        let model_ex_info = self.read_model_ex_info()?;
-
-        Ok(Model {
-            header,
-            vertices,
-            triangles,
-            groups,
-            materials,
-            key_frame_data,
-            joints,
-            comments,
-            vertex_ex_info,
-            joint_ex_info,
-            model_ex_info,
-        })
+        let mut model = Model::default();
+        self.read_sections(&mut model)?;
+        Ok(model)
+    }
+
+    fn read_model_recover(&mut self) -> Recovery {
+        let mut model = Model::default();
+        match self.read_sections(&mut model) {
+            Ok(()) => Recovery {
+                model,
+                error: None,
+                section: None,
+            },
+            Err(error) => Recovery {
+                model,
+                error: Some(error),
+                section: Some(self.section),
+            },
+        }
+    }
+
+    /// Read each section into `model` in turn, so that the sections before an
+    /// error are kept.
+    fn read_sections(&mut self, model: &mut Model) -> Result<()> {
+        model.header = self.read_header()?;
+        model.vertices = self.read_vertices()?;
+        model.triangles = self.read_triangles()?;
+        self.check_reference_counts(&model.vertices, &model.triangles);
+        model.groups = self.read_groups()?;
+        model.materials = self.read_materials()?;
+        model.key_frame_data = self.read_key_frame_data()?;
+        model.joints = self.read_joints()?;
+        model.comments = self.read_optional(Self::read_comments)?;
+        let num_vertices = model.vertices.len();
+        model.vertex_ex_info = self.read_optional(|this| this.read_vertex_ex_info(num_vertices))?;
+        let num_joints = model.joints.len();
+        model.joint_ex_info = self.read_optional(|this| this.read_joint_ex_info(num_joints))?;
+        model.model_ex_info = self.read_optional(Self::read_model_ex_info)?;
+        model.unknown_trailer = self.read_trailer()?;
+        Ok(())
     }
 
     fn read_header(&mut self) -> Result<Header> {
-        let de::Header { id, version } = unsafe { self.read_type()? };
-        ensure!(id == "MS3D000000".as_bytes(), "invalid header");
-        ensure!(version == 4, "unsupported version {}", version);
+        self.begin_section(Section::Header);
+        let offset = self.rdr.position();
+        let de::Header { id, version } = self.read_type()?;
+        if id != "MS3D000000".as_bytes() {
+            return Err(Error::BadMagic { offset });
+        }
+        if version != 3 && version != 4 {
+            return Err(Error::UnsupportedVersion {
+                version,
+                offset: offset + 10,
+            });
+        }
+        self.version = version;
         Ok(Header { version })
     }
 
     fn read_vertices(&mut self) -> Result<Vec<Vertex>> {
+        self.begin_section(Section::Vertices);
         let len = self.read_u16()? as usize;
-        self.read_vec(len, Self::read_vertex)
+        self.read_elements(len, de::Vertex::SIZE, Self::read_vertex)
     }
 
     fn read_vertex(&mut self) -> Result<Vertex> {
@@ -88,8 +202,8 @@ impl<R: BufReadExact> Reader<R> {
             vertex,
             bone_id,
             reference_count,
-        } = unsafe { self.read_type()? };
-        let flags = convert_flags(flags, Vertex::ALLOWED_FLAGS)?;
+        } = self.read_type()?;
+        let flags = self.convert_flags(flags, Vertex::ALLOWED_FLAGS)?;
         Ok(Vertex {
             flags,
             vertex,
@@ -99,8 +213,9 @@ impl<R: BufReadExact> Reader<R> {
     }
 
     fn read_triangles(&mut self) -> Result<Vec<Triangle>> {
+        self.begin_section(Section::Triangles);
         let len = self.read_u16()? as usize;
-        self.read_vec(len, Self::read_triangle)
+        self.read_elements(len, de::Triangle::SIZE, Self::read_triangle)
     }
 
     fn read_triangle(&mut self) -> Result<Triangle> {
@@ -112,10 +227,9 @@ impl<R: BufReadExact> Reader<R> {
             t,
             smoothing_group,
             group_index,
-        } = unsafe { self.read_type()? };
-        let flags = convert_flags(flags as u8, Triangle::ALLOWED_FLAGS)?;
+        } = self.read_type()?;
         Ok(Triangle {
-            flags,
+            flags: self.convert_flags(flags, Triangle::ALLOWED_FLAGS)?,
             vertex_indices,
             vertex_normals,
             s,
@@ -125,35 +239,72 @@ impl<R: BufReadExact> Reader<R> {
         })
     }
 
+    /// Compare the reference count of each vertex with the number of triangle
+    /// corners which use it.
+    fn check_reference_counts(&mut self, vertices: &[Vertex], triangles: &[Triangle]) {
+        let counts = repair::reference_counts(vertices.len(), triangles);
+        for (index, (vertex, actual)) in vertices.iter().zip(counts).enumerate() {
+            if vertex.reference_count != actual {
+                self.diagnostics.push(Diagnostic {
+                    section: Section::Vertices,
+                    index,
+                    kind: DiagnosticKind::ReferenceCountMismatch {
+                        reference_count: vertex.reference_count,
+                        actual,
+                    },
+                });
+            }
+        }
+    }
+
     fn read_groups(&mut self) -> Result<Vec<Group>> {
+        self.begin_section(Section::Groups);
         let len = self.read_u16()? as usize;
-        self.read_vec(len, Self::read_group)
+        self.read_elements(len, GROUP_MIN_SIZE, Self::read_group)
     }
 
     fn read_group(&mut self) -> Result<Group> {
+        let (mut group, num_triangles) = self.read_group_prefix()?;
+        group.triangle_indices = self.read_vec(num_triangles as usize, 2, Self::read_u16)?;
+        group.material_index = self.read_group_suffix()?;
+        Ok(group)
+    }
+
+    /// Read the fixed size start of a group, returning the group without its
+    /// triangles or material and the number of triangle indices which follow.
+    fn read_group_prefix(&mut self) -> Result<(Group, u16)> {
         let de::GroupPrefix {
             flags,
             name,
             num_triangles,
-        } = unsafe { self.read_type()? };
-
-        let flags = convert_flags(flags, Group::ALLOWED_FLAGS)?;
-        let name = convert_string(&name)?;
-        let triangle_indices = self.read_vec(num_triangles as usize, Self::read_u16)?;
+        } = self.read_type()?;
 
-        let de::GroupSuffix { material_index } = unsafe { self.read_type()? };
+        let flags = self.convert_flags(flags, Group::ALLOWED_FLAGS)?;
+        let raw_name = name.to_vec();
+        let name = self.convert_string(&name)?;
+        if name.is_empty() {
+            self.diagnose(DiagnosticKind::EmptyGroupName);
+        }
 
-        Ok(Group {
+        let group = Group {
             flags,
             name,
-            triangle_indices,
-            material_index,
-        })
+            raw_name,
+            triangle_indices: Vec::new(),
+            material_index: -1,
+        };
+        Ok((group, num_triangles))
+    }
+
+    fn read_group_suffix(&mut self) -> Result<i8> {
+        let de::GroupSuffix { material_index } = self.read_type()?;
+        Ok(material_index)
     }
 
     fn read_materials(&mut self) -> Result<Vec<Material>> {
+        self.begin_section(Section::Materials);
         let len = self.read_u16()? as usize;
-        self.read_vec(len, Self::read_material)
+        self.read_elements(len, de::Material::SIZE, Self::read_material)
     }
 
     fn read_material(&mut self) -> Result<Material> {
@@ -168,14 +319,18 @@ impl<R: BufReadExact> Reader<R> {
             mode,
             texture,
             alphamap,
-        } = unsafe { self.read_type()? };
+        } = self.read_type()?;
 
-        let name = convert_string(&name)?;
-        let texture = convert_path(&texture)?;
-        let alphamap = convert_path(&alphamap)?;
+        let raw_name = name.to_vec();
+        let name = self.convert_string(&name)?;
+        let raw_texture = texture.to_vec();
+        let texture = self.convert_path(&texture)?;
+        let raw_alphamap = alphamap.to_vec();
+        let alphamap = self.convert_path(&alphamap)?;
 
         Ok(Material {
             name,
+            raw_name,
             ambient,
             diffuse,
             specular,
@@ -184,29 +339,53 @@ impl<R: BufReadExact> Reader<R> {
             transparency,
             mode,
             texture,
+            raw_texture,
             alphamap,
+            raw_alphamap,
         })
     }
 
     fn read_key_frame_data(&mut self) -> Result<KeyFrameData> {
+        self.begin_section(Section::KeyFrameData);
         let de::KeyFrameData {
             animation_fps,
             current_time,
             total_frames,
-        } = unsafe { self.read_type()? };
-        Ok(KeyFrameData {
+        } = self.read_type()?;
+        let key_frame_data = KeyFrameData {
             animation_fps,
             current_time,
             total_frames,
-        })
+        };
+        self.key_frame_data = Some(key_frame_data.clone());
+        Ok(key_frame_data)
     }
 
     fn read_joints(&mut self) -> Result<Vec<Joint>> {
+        self.begin_section(Section::Joints);
         let len = self.read_u16()? as usize;
-        self.read_vec(len, Self::read_joint)
+        self.read_elements(len, de::JointPrefix::SIZE, Self::read_joint)
     }
 
     fn read_joint(&mut self) -> Result<Joint> {
+        let (mut joint, num_key_frames_rot, num_key_frames_trans) = self.read_joint_prefix()?;
+        joint.key_frames_rot = self.read_vec(
+            num_key_frames_rot as usize,
+            de::KeyFrameRot::SIZE,
+            Self::read_key_frame_rot,
+        )?;
+        joint.key_frames_trans = self.read_vec(
+            num_key_frames_trans as usize,
+            de::KeyFramePos::SIZE,
+            Self::read_key_frame_pos,
+        )?;
+        Ok(joint)
+    }
+
+    /// Read the fixed size start of a joint, returning the joint without its
+    /// key frames and the number of rotation and translation key frames which
+    /// follow.
+    fn read_joint_prefix(&mut self) -> Result<(Joint, u16, u16)> {
         let de::JointPrefix {
             flags,
             name,
@@ -215,89 +394,181 @@ impl<R: BufReadExact> Reader<R> {
             position,
             num_key_frames_rot,
             num_key_frames_trans,
-        } = unsafe { self.read_type()? };
-
-        let flags = convert_flags(flags, Joint::ALLOWED_FLAGS)?;
-        let name = convert_string(&name)?;
-        let parent_name = convert_string(&parent_name)?;
-
-        let key_frames_rot = self.read_vec(num_key_frames_rot as usize, Self::read_key_frame_rot)?;
-        let key_frames_trans =
-            self.read_vec(num_key_frames_trans as usize, Self::read_key_frame_pos)?;
+        } = self.read_type()?;
+
+        let flags = self.convert_flags(flags, Joint::ALLOWED_FLAGS)?;
+        let raw_name = name.to_vec();
+        let name = self.convert_string(&name)?;
+        let raw_parent_name = parent_name.to_vec();
+        let parent_name = self.convert_string(&parent_name)?;
+        let total_frames = self.key_frame_data.as_ref().map(|data| data.total_frames);
+        if total_frames == Some(0) && (num_key_frames_rot > 0 || num_key_frames_trans > 0) {
+            self.diagnose(DiagnosticKind::NoFrames);
+        }
 
-        Ok(Joint {
+        let joint = Joint {
             flags,
             name,
+            raw_name,
             parent_name,
+            raw_parent_name,
             rotation,
             position,
-            key_frames_rot,
-            key_frames_trans,
-        })
+            key_frames_rot: Vec::new(),
+            key_frames_trans: Vec::new(),
+        };
+        Ok((joint, num_key_frames_rot, num_key_frames_trans))
     }
 
     fn read_key_frame_rot(&mut self) -> Result<KeyFrameRot> {
-        let de::KeyFrameRot { time, rotation } = unsafe { self.read_type()? };
+        let de::KeyFrameRot { time, rotation } = self.read_type()?;
+        self.check_key_frame_time(time);
         Ok(KeyFrameRot { time, rotation })
     }
 
     fn read_key_frame_pos(&mut self) -> Result<KeyFramePos> {
-        let de::KeyFramePos { time, position } = unsafe { self.read_type()? };
+        let de::KeyFramePos { time, position } = self.read_type()?;
+        self.check_key_frame_time(time);
         Ok(KeyFramePos { time, position })
     }
 
-    fn read_comments(&mut self) -> Result<Comments> {
-        let sub_version = self.read_i32()?;
-        ensure!(
-            sub_version == 1,
-            "unsupported comment sub-version {}",
-            sub_version
-        );
-        let len = self.read_u32()? as usize;
-        let group_comments = self.read_vec(len, Self::read_comment)?;
-        let len = self.read_i32()? as usize;
-        let material_comments = self.read_vec(len, Self::read_comment)?;
-        let len = self.read_i32()? as usize;
-        let joint_comments = self.read_vec(len, Self::read_comment)?;
-        let len = self.read_i32()? as usize;
-        let model_comment = match len {
-            0 => None,
-            1 => Some(self.read_comment()?),
-            _ => bail!("invalid number of model comments"),
+    /// Check that a key frame of the current joint is within the animation,
+    /// which is `total_frames / animation_fps` seconds long.
+    fn check_key_frame_time(&mut self, time: f32) {
+        let length = match self.key_frame_data {
+            Some(ref data) if data.total_frames > 0 && data.animation_fps > 0.0 => {
+                data.total_frames as f32 / data.animation_fps
+            }
+            _ => return,
         };
+        if time < 0.0 || time > length {
+            self.diagnose(DiagnosticKind::KeyFrameOutOfRange { time });
+        }
+    }
 
-        Ok(Comments {
+    fn read_comments(&mut self) -> Result<Option<Comments>> {
+        self.begin_section(Section::Comments);
+        let sub_version = match self.read_sub_version(&[1])? {
+            Some(sub_version) => sub_version,
+            None => return Ok(None),
+        };
+        let mut total = 0;
+        let len = self.read_comment_count(&mut total)?;
+        let group_comments = self.read_elements(len, de::CommentPrefix::SIZE, Self::read_comment)?;
+        let len = self.read_comment_count(&mut total)?;
+        let material_comments =
+            self.read_elements(len, de::CommentPrefix::SIZE, Self::read_comment)?;
+        let len = self.read_comment_count(&mut total)?;
+        let joint_comments = self.read_elements(len, de::CommentPrefix::SIZE, Self::read_comment)?;
+        let len = self.read_model_comment_count(&mut total)?;
+        let model_comment = self
+            .read_elements(len, de::CommentPrefix::SIZE, Self::read_comment)?
+            .pop();
+
+        Ok(Some(Comments {
             sub_version,
             group_comments,
             material_comments,
             joint_comments,
             model_comment,
-        })
+        }))
+    }
+
+    /// Read the number of comments in one of the lists in the comments section,
+    /// checking it against the limit on the total number of comments.
+    fn read_comment_count(&mut self, total: &mut usize) -> Result<usize> {
+        let offset = self.rdr.position();
+        let count = i64::from(self.read_i32()?);
+        if count < 0 {
+            return Err(Error::InvalidCount {
+                section: self.section,
+                count,
+                offset,
+            });
+        }
+        if *total as u64 + count as u64 > self.options.limits.max_comments as u64 {
+            return Err(Error::LimitExceeded {
+                section: self.section,
+                limit: Limit::Comments,
+                offset,
+            });
+        }
+        *total += count as usize;
+        Ok(count as usize)
+    }
+
+    /// Read the number of model comments, which must be 0 or 1.
+    fn read_model_comment_count(&mut self, total: &mut usize) -> Result<usize> {
+        let offset = self.rdr.position();
+        let count = self.read_comment_count(total)?;
+        if count > 1 {
+            return Err(Error::InvalidCount {
+                section: self.section,
+                count: count as i64,
+                offset,
+            });
+        }
+        Ok(count)
     }
 
     fn read_comment(&mut self) -> Result<Comment> {
+        let (index, len) = self.read_comment_prefix()?;
+        let offset = self.rdr.position();
+        let raw_comment = self.read_bytes(len)?.to_vec();
+        let comment = self.decode_string(&raw_comment, offset)?;
+        Ok(Comment {
+            index,
+            comment,
+            raw_comment,
+        })
+    }
+
+    /// Read the index and length of a comment, checking that the comment fits
+    /// in the rest of the file.
+    fn read_comment_prefix(&mut self) -> Result<(i32, usize)> {
         let de::CommentPrefix {
             index,
             comment_length,
-        } = unsafe { self.read_type()? };
-        let comment = self.read_string(comment_length as usize)?;
-        Ok(Comment { index, comment })
+        } = self.read_type()?;
+        let offset = self.rdr.position() - 4;
+        if comment_length < 0 {
+            return Err(Error::InvalidCount {
+                section: self.section,
+                count: comment_length.into(),
+                offset,
+            });
+        }
+        if comment_length as usize > self.options.limits.max_comment_length {
+            return Err(Error::LimitExceeded {
+                section: self.section,
+                limit: Limit::CommentLength,
+                offset,
+            });
+        }
+        self.check_len(comment_length as usize, 1)?;
+        Ok((index, comment_length as usize))
     }
 
-    fn read_vertex_ex_info(&mut self, len: usize) -> Result<VertexExInfo> {
+    fn read_vertex_ex_info(&mut self, len: usize) -> Result<Option<VertexExInfo>> {
         use VertexExInfo::*;
 
-        let sub_version = self.read_i32()?;
-        match sub_version {
-            1 => Ok(SubVersion1(self.read_vec(len, Self::read_vertex_ex_1)?)),
-            2 => Ok(SubVersion2(self.read_vec(len, Self::read_vertex_ex_2)?)),
-            3 => Ok(SubVersion3(self.read_vec(len, Self::read_vertex_ex_3)?)),
-            v => bail!("unsupported vertex ex sub-version {}", v),
+        self.begin_section(Section::VertexExInfo);
+        match self.read_sub_version(&[1, 2, 3])? {
+            Some(1) => Ok(Some(SubVersion1(
+                self.read_elements(len, de::VertexEx1::SIZE, Self::read_vertex_ex_1)?,
+            ))),
+            Some(2) => Ok(Some(SubVersion2(
+                self.read_elements(len, de::VertexEx2::SIZE, Self::read_vertex_ex_2)?,
+            ))),
+            Some(_) => Ok(Some(SubVersion3(
+                self.read_elements(len, de::VertexEx3::SIZE, Self::read_vertex_ex_3)?,
+            ))),
+            None => Ok(None),
         }
     }
 
     fn read_vertex_ex_1(&mut self) -> Result<VertexEx1> {
-        let de::VertexEx1 { bone_ids, weights } = unsafe { self.read_type()? };
+        let de::VertexEx1 { bone_ids, weights } = self.read_type()?;
         Ok(VertexEx1 { bone_ids, weights })
     }
 
@@ -306,7 +577,7 @@ impl<R: BufReadExact> Reader<R> {
             bone_ids,
             weights,
             extra,
-        } = unsafe { self.read_type()? };
+        } = self.read_type()?;
         Ok(VertexEx2 {
             bone_ids,
             weights,
@@ -319,7 +590,7 @@ impl<R: BufReadExact> Reader<R> {
             bone_ids,
             weights,
             extra,
-        } = unsafe { self.read_type()? };
+        } = self.read_type()?;
         Ok(VertexEx3 {
             bone_ids,
             weights,
@@ -327,37 +598,35 @@ impl<R: BufReadExact> Reader<R> {
         })
     }
 
-    fn read_joint_ex_info(&mut self, len: usize) -> Result<JointExInfo> {
-        let sub_version = self.read_i32()?;
-        ensure!(
-            sub_version == 1,
-            "unsupported joint ex sub-version {}",
-            sub_version
-        );
-        let joint_ex = self.read_vec(len, Self::read_joint_ex)?;
-        Ok(JointExInfo {
+    fn read_joint_ex_info(&mut self, len: usize) -> Result<Option<JointExInfo>> {
+        self.begin_section(Section::JointExInfo);
+        let sub_version = match self.read_sub_version(&[1])? {
+            Some(sub_version) => sub_version,
+            None => return Ok(None),
+        };
+        let joint_ex = self.read_elements(len, de::JointEx::SIZE, Self::read_joint_ex)?;
+        Ok(Some(JointExInfo {
             sub_version,
             joint_ex,
-        })
+        }))
     }
 
     fn read_joint_ex(&mut self) -> Result<JointEx> {
-        let de::JointEx { color } = unsafe { self.read_type()? };
+        let de::JointEx { color } = self.read_type()?;
         Ok(JointEx { color })
     }
 
-    fn read_model_ex_info(&mut self) -> Result<ModelExInfo> {
-        let sub_version = self.read_i32()?;
-        ensure!(
-            sub_version == 1,
-            "unsupported model ex sub-version {}",
-            sub_version
-        );
+    fn read_model_ex_info(&mut self) -> Result<Option<ModelExInfo>> {
+        self.begin_section(Section::ModelExInfo);
+        let sub_version = match self.read_sub_version(&[1])? {
+            Some(sub_version) => sub_version,
+            None => return Ok(None),
+        };
         let model_ex = self.read_model_ex()?;
-        Ok(ModelExInfo {
+        Ok(Some(ModelExInfo {
             sub_version,
             model_ex,
-        })
+        }))
     }
 
     fn read_model_ex(&mut self) -> Result<ModelEx> {
@@ -365,7 +634,7 @@ impl<R: BufReadExact> Reader<R> {
             joint_size,
             transparency_mode,
             alpha_ref,
-        } = unsafe { self.read_type()? };
+        } = self.read_type()?;
         Ok(ModelEx {
             joint_size,
             transparency_mode,
@@ -373,54 +642,265 @@ impl<R: BufReadExact> Reader<R> {
         })
     }
 
-    fn read_string(&mut self, len: usize) -> Result<String> {
-        Ok(str::from_utf8(self.rdr.buf_read_exact(len)?)?.to_owned())
+    /// Read one of the optional sections at the end of the file. Older files
+    /// may end before any of these sections, so running out of input at the
+    /// start of the section is not an error. These sections are not parsed
+    /// in version 3 files, whose data after the joints is kept as the
+    /// trailer.
+    fn read_optional<T, F>(&mut self, f: F) -> Result<Option<T>>
+    where
+        F: FnOnce(&mut Self) -> Result<Option<T>>,
+    {
+        if self.skipped.is_some() || self.version < 4 {
+            return Ok(None);
+        }
+        let offset = self.rdr.position();
+        let at_eof = self
+            .rdr
+            .at_eof()
+            .map_err(|error| Error::Io { offset, error })?;
+        if at_eof {
+            Ok(None)
+        } else {
+            f(self)
+        }
+    }
+
+    /// Read any data after the last section which was understood, starting
+    /// with the sub-version of a skipped section.
+    fn read_trailer(&mut self) -> Result<Vec<u8>> {
+        let mut trailer = match self.skipped {
+            Some(sub_version) => sub_version.to_le_bytes().to_vec(),
+            None => Vec::new(),
+        };
+        let offset = self.rdr.position();
+        let limit = self.options.limits.max_total_bytes.saturating_sub(offset);
+        let at_eof = self
+            .rdr
+            .read_to_end(&mut trailer, limit)
+            .and_then(|()| self.rdr.at_eof())
+            .map_err(|error| Error::Io { offset, error })?;
+        if !at_eof {
+            return Err(Error::LimitExceeded {
+                section: self.section,
+                limit: Limit::TotalBytes,
+                offset: self.rdr.position(),
+            });
+        }
+        Ok(trailer)
+    }
+
+    /// Read the sub-version of an optional section. Returns `None` if the
+    /// sub-version is unsupported and the section should be skipped.
+    fn read_sub_version(&mut self, supported: &[i32]) -> Result<Option<i32>> {
+        let offset = self.rdr.position();
+        let sub_version = self.read_i32()?;
+        if supported.contains(&sub_version) {
+            Ok(Some(sub_version))
+        } else if self.options.strict {
+            Err(Error::UnsupportedSubVersion {
+                section: self.section,
+                sub_version,
+                offset,
+            })
+        } else {
+            self.warn(offset, WarningKind::UnsupportedSubVersion { sub_version });
+            self.skipped = Some(sub_version);
+            Ok(None)
+        }
+    }
+
+    fn warn(&mut self, offset: u64, kind: WarningKind) {
+        self.warnings.push(Warning {
+            section: self.section,
+            index: self.index,
+            offset,
+            kind,
+        });
+    }
+
+    /// Record an oddity in the current element which is allowed even in
+    /// strict mode.
+    fn diagnose(&mut self, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic {
+            section: self.section,
+            index: self.index,
+            kind,
+        });
+    }
+
+    fn begin_section(&mut self, section: Section) {
+        self.section = section;
+        self.index = 0;
+        self.offset = self.rdr.position();
+    }
+
+    /// Read the elements of the current section, keeping track of the index
+    /// of the current element for error reporting. Each element is at least
+    /// `size` bytes long.
+    fn read_elements<T, F>(&mut self, len: usize, size: usize, f: F) -> Result<Vec<T>>
+    where
+        F: Fn(&mut Self) -> Result<T>,
+    {
+        self.check_len(len, size)?;
+        (0..len)
+            .map(|index| {
+                self.index = index;
+                self.offset = self.rdr.position();
+                f(self)
+            })
+            .collect()
+    }
+
+    /// Like `read_elements`, but discards each element after it is read.
+    fn for_each_element<F>(&mut self, len: usize, size: usize, mut f: F) -> Result<()>
+    where
+        F: FnMut(&mut Self) -> Result<()>,
+    {
+        self.check_len(len, size)?;
+        for index in 0..len {
+            self.index = index;
+            self.offset = self.rdr.position();
+            f(self)?;
+        }
+        Ok(())
     }
 
-    fn read_vec<T, F>(&mut self, len: usize, f: F) -> Result<Vec<T>>
+    fn read_vec<T, F>(&mut self, len: usize, size: usize, f: F) -> Result<Vec<T>>
     where
         F: Fn(&mut Self) -> Result<T>,
     {
+        self.check_len(len, size)?;
         (0..len).map(|_| f(self)).collect()
     }
 
     fn read_u16(&mut self) -> Result<u16> {
-        unsafe { self.read_type() }
+        self.read_type()
     }
 
-    fn read_u32(&mut self) -> Result<u32> {
-        unsafe { self.read_type() }
+    fn read_i32(&mut self) -> Result<i32> {
+        self.read_type()
     }
 
-    fn read_i32(&mut self) -> Result<i32> {
-        unsafe { self.read_type() }
+    fn read_type<T: Le>(&mut self) -> Result<T> {
+        Ok(T::read_le(self.read_bytes(T::SIZE)?))
     }
 
-    unsafe fn read_type<T>(&mut self) -> Result<T> {
-        Ok(ptr::read_unaligned(
-            self.rdr.buf_read_exact(mem::size_of::<T>())? as *const [u8] as *const T,
-        ))
+    /// Check that `len` elements of `size` bytes could fit in the remaining
+    /// input, so that lengths read from the file are rejected before any
+    /// memory is allocated for them.
+    fn check_len(&self, len: usize, size: usize) -> Result<()> {
+        let offset = self.rdr.position();
+        let bytes = (len as u64).saturating_mul(size as u64);
+        if offset.saturating_add(bytes) > self.options.limits.max_total_bytes {
+            return Err(Error::LimitExceeded {
+                section: self.section,
+                limit: Limit::TotalBytes,
+                offset,
+            });
+        }
+        match self.rdr.remaining() {
+            Some(remaining) if bytes > remaining => Err(Error::UnexpectedEof {
+                section: self.section,
+                offset,
+            }),
+            _ => Ok(()),
+        }
     }
-}
 
-fn convert_string(bytes: &[u8]) -> Result<String> {
-    let vec = if let Some(i) = memchr(0, bytes) {
-        bytes[..i].to_owned()
-    } else {
-        bytes.to_owned()
-    };
-    Ok(String::from_utf8(vec)?)
+    fn read_bytes(&mut self, len: usize) -> Result<&[u8]> {
+        self.check_len(len, 1)?;
+        let section = self.section;
+        let offset = self.rdr.position();
+        self.rdr
+            .buf_read_exact(len)
+            .map_err(|error| read_error(section, offset, error))
+    }
+
+    fn skip_bytes(&mut self, len: usize) -> Result<()> {
+        self.check_len(len, 1)?;
+        let section = self.section;
+        let offset = self.rdr.position();
+        self.rdr
+            .skip(len)
+            .map_err(|error| read_error(section, offset, error))
+    }
+
+    /// Convert a name or path read as part of the element most recently read.
+    fn convert_string(&mut self, bytes: &[u8]) -> Result<String> {
+        let offset = self.offset;
+        self.decode_string(trim_nul(bytes), offset)
+    }
+
+    fn convert_path(&mut self, bytes: &[u8]) -> Result<PathBuf> {
+        self.convert_string(bytes).map(Into::into)
+    }
+
+    /// Check that a name or path read as part of the element most recently
+    /// read can be decoded, without decoding it.
+    fn check_string(&mut self, bytes: &[u8]) -> Result<()> {
+        if self.options.encoding.is_valid(trim_nul(bytes)) {
+            return Ok(());
+        }
+        let offset = self.offset;
+        self.invalid_text(offset)
+    }
+
+    fn decode_string(&mut self, bytes: &[u8], offset: u64) -> Result<String> {
+        let (string, had_errors) = self.options.encoding.decode(bytes);
+        if had_errors {
+            self.invalid_text(offset)?;
+        }
+        Ok(string)
+    }
+
+    fn invalid_text(&mut self, offset: u64) -> Result<()> {
+        if self.options.strict {
+            return Err(Error::InvalidText {
+                section: self.section,
+                index: self.index,
+                offset,
+            });
+        }
+        self.warn(offset, WarningKind::InvalidText);
+        Ok(())
+    }
+
+    /// Check flags against those allowed for an element. Triangle flags are
+    /// stored in 16 bits, but all of the known flags are in the low byte.
+    fn convert_flags<T: Into<u16>>(&mut self, bits: T, allowed: Flags) -> Result<Flags> {
+        let bits = bits.into();
+        if let Some(flags) = Flags::from_bits(bits) {
+            if allowed.contains(flags) {
+                return Ok(flags);
+            }
+        }
+        if self.options.strict {
+            return Err(Error::InvalidFlags {
+                section: self.section,
+                index: self.index,
+                bits,
+                offset: self.offset,
+            });
+        }
+        let offset = self.offset;
+        self.warn(offset, WarningKind::InvalidFlags { bits });
+        Ok(Flags::from_raw_bits(bits))
+    }
 }
 
-fn convert_path(bytes: &[u8]) -> Result<PathBuf> {
-    convert_string(bytes).map(Into::into)
+fn read_error(section: Section, offset: u64, error: io::Error) -> Error {
+    if error.kind() == io::ErrorKind::UnexpectedEof {
+        Error::UnexpectedEof { section, offset }
+    } else {
+        Error::Io { offset, error }
+    }
 }
 
-fn convert_flags(bits: u8, allowed: Flags) -> Result<Flags> {
-    if let Some(flags) = Flags::from_bits(bits) {
-        if allowed.contains(flags) {
-            return Ok(flags);
-        }
+/// Strip the nul terminator and any padding after it from a fixed size string.
+fn trim_nul(bytes: &[u8]) -> &[u8] {
+    match memchr(0, bytes) {
+        Some(i) => &bytes[..i],
+        None => bytes,
     }
-    Err(format_err!("invalid flags {}", bits))
 }
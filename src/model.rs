@@ -1,10 +1,16 @@
-use std::path::PathBuf;
+use std::fs::File;
+use std::path::{Path, PathBuf};
 use std::io;
 
-use super::{Reader, Result};
+use super::{Diagnostic, Error, ParseOptions, Reader, Result, Section, Warning, WriteOptions};
+use write::Writer;
 
 /// Represents an ms3d model file.
-#[derive(Clone, Debug)]
+///
+/// The comments, vertex extra, joint extra and model extra sections were added in
+/// later versions of MilkShape, so files written by older versions may end before
+/// them. Each of these sections is `None` if it was not present in the file.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Model {
     pub header: Header,
     pub vertices: Vec<Vertex>,
@@ -13,31 +19,200 @@ pub struct Model {
     pub materials: Vec<Material>,
     pub key_frame_data: KeyFrameData,
     pub joints: Vec<Joint>,
-    pub comments: Comments,
-    pub vertex_ex_info: VertexExInfo,
-    pub joint_ex_info: JointExInfo,
-    pub model_ex_info: ModelExInfo,
+    pub comments: Option<Comments>,
+    pub vertex_ex_info: Option<VertexExInfo>,
+    pub joint_ex_info: Option<JointExInfo>,
+    pub model_ex_info: Option<ModelExInfo>,
+    /// Any data after the last section which was understood. This is either
+    /// data appended after the model extra info section by a third-party
+    /// plugin, or an optional section with an unsupported sub-version skipped
+    /// in lenient mode along with everything after it. It is written back
+    /// unchanged after the last section.
+    pub unknown_trailer: Vec<u8>,
 }
 
 impl Model {
     /// Read an ms3d model file from a reader.
     pub fn from_reader<R: io::Read>(rdr: R) -> Result<Self> {
-        Reader::from_io_reader(rdr).read_model()
+        Reader::from_io_reader(rdr, ParseOptions::default()).read_model()
     }
 
     /// Read an ms3d model file from a slice of bytes.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
-        Reader::from_slice(bytes).read_model()
+        Reader::from_slice(bytes, ParseOptions::default()).read_model()
     }
+
+    /// Read an ms3d model file from the file at `path`. Errors are wrapped in
+    /// an [`Error::File`](enum.Error.html#variant.File) holding the path.
+    ///
+    /// With the `mmap` feature enabled, the file is memory mapped and parsed
+    /// in place, falling back to buffered reads if it cannot be mapped. The
+    /// file must not be modified by another process while it is being read.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        open_file(path).map_err(|error| Error::File {
+            path: path.to_owned(),
+            error: Box::new(error),
+        })
+    }
+
+    /// Read an ms3d model file from a reader, returning diagnostics for any
+    /// oddities which are allowed even in strict mode, such as key frames
+    /// outside the animation or incorrect vertex reference counts.
+    pub fn from_reader_diagnostic<R: io::Read>(rdr: R) -> Result<(Self, Vec<Diagnostic>)> {
+        let (model, _, diagnostics) =
+            Self::from_reader_diagnostic_with(rdr, ParseOptions::default())?;
+        Ok((model, diagnostics))
+    }
+
+    /// Read an ms3d model file from a slice of bytes, returning diagnostics for
+    /// any oddities which are allowed even in strict mode.
+    pub fn from_bytes_diagnostic(bytes: &[u8]) -> Result<(Self, Vec<Diagnostic>)> {
+        let (model, _, diagnostics) =
+            Self::from_bytes_diagnostic_with(bytes, ParseOptions::default())?;
+        Ok((model, diagnostics))
+    }
+
+    /// Read an ms3d model file from a reader with the given options, returning
+    /// both warnings and diagnostics.
+    pub fn from_reader_diagnostic_with<R: io::Read>(
+        rdr: R,
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<Warning>, Vec<Diagnostic>)> {
+        Reader::from_io_reader(rdr, options).read_model_with_diagnostics()
+    }
+
+    /// Read an ms3d model file from a slice of bytes with the given options,
+    /// returning both warnings and diagnostics.
+    pub fn from_bytes_diagnostic_with(
+        bytes: &[u8],
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<Warning>, Vec<Diagnostic>)> {
+        Reader::from_slice(bytes, options).read_model_with_diagnostics()
+    }
+
+    /// Read an ms3d model file from a reader, keeping the sections decoded
+    /// before any error.
+    ///
+    /// If the file is truncated or corrupt, the returned
+    /// [`Recovery`](struct.Recovery.html) holds every section which was fully
+    /// decoded along with the error. The section in which the error occurred
+    /// and all later sections are left empty.
+    pub fn from_reader_recover<R: io::Read>(rdr: R) -> Recovery {
+        Reader::from_io_reader(rdr, ParseOptions::default()).read_model_recover()
+    }
+
+    /// Read an ms3d model file from a slice of bytes, keeping the sections
+    /// decoded before any error.
+    pub fn from_bytes_recover(bytes: &[u8]) -> Recovery {
+        Reader::from_slice(bytes, ParseOptions::default()).read_model_recover()
+    }
+
+    /// Read an ms3d model file from a reader with the given options, returning
+    /// any warnings for problems that were ignored.
+    pub fn from_reader_with<R: io::Read>(
+        rdr: R,
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<Warning>)> {
+        Reader::from_io_reader(rdr, options).read_model_with_warnings()
+    }
+
+    /// Read an ms3d model file from a slice of bytes with the given options,
+    /// returning any warnings for problems that were ignored.
+    pub fn from_bytes_with(bytes: &[u8], options: ParseOptions) -> Result<(Self, Vec<Warning>)> {
+        Reader::from_slice(bytes, options).read_model_with_warnings()
+    }
+
+    /// Write the model in the ms3d format to a writer.
+    pub fn write_to<W: io::Write>(&self, wtr: W) -> Result<()> {
+        self.write_to_with(wtr, WriteOptions::default())
+    }
+
+    /// Write the model in the ms3d format to a writer with the given options.
+    pub fn write_to_with<W: io::Write>(&self, wtr: W, options: WriteOptions) -> Result<()> {
+        Writer::new(wtr, options).write_model(self)
+    }
+
+    /// Write the model in the ms3d format to a vector of bytes.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(feature = "mmap")]
+fn open_file(path: &Path) -> Result<Model> {
+    let file = File::open(path).map_err(|error| Error::Io { offset: 0, error })?;
+    // The map is only read from until parsing is done, and the parser does
+    // not rely on the contents being valid.
+    match unsafe { ::memmap2::Mmap::map(&file) } {
+        Ok(map) => Model::from_bytes(&map),
+        Err(_) => Model::from_reader(file),
+    }
+}
+
+#[cfg(not(feature = "mmap"))]
+fn open_file(path: &Path) -> Result<Model> {
+    let file = File::open(path).map_err(|error| Error::Io { offset: 0, error })?;
+    Model::from_reader(file)
 }
 
-#[derive(Clone, Debug)]
+/// An empty model, with MilkShape's default animation settings.
+impl Default for Model {
+    fn default() -> Self {
+        Model {
+            header: Header { version: 4 },
+            vertices: Vec::new(),
+            triangles: Vec::new(),
+            groups: Vec::new(),
+            materials: Vec::new(),
+            key_frame_data: KeyFrameData {
+                animation_fps: 24.0,
+                current_time: 1.0,
+                total_frames: 30,
+            },
+            joints: Vec::new(),
+            comments: None,
+            vertex_ex_info: None,
+            joint_ex_info: None,
+            model_ex_info: None,
+            unknown_trailer: Vec::new(),
+        }
+    }
+}
+
+/// The part of a model which could be decoded before an error.
+#[derive(Debug)]
+pub struct Recovery {
+    /// The sections decoded before the error, or the whole model if there was
+    /// no error.
+    pub model: Model,
+    /// The error which stopped decoding, if any.
+    pub error: Option<Error>,
+    /// The section in which decoding stopped, if there was an error.
+    pub section: Option<Section>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Header {
+    /// The format version, either 3 or 4.
+    ///
+    /// Version 3 files were written by MilkShape 1.3 and 1.4, and have the
+    /// same layout as version 4 files up to the end of the joints. This crate
+    /// does not parse the optional sections of a version 3 file: any data
+    /// after the joints is kept in
+    /// [`Model::unknown_trailer`](struct.Model.html#structfield.unknown_trailer)
+    /// and written back unchanged, and writing a version 3 model with any
+    /// optional sections set is an
+    /// [`Error::UnsupportedSection`](enum.Error.html#variant.UnsupportedSection).
     pub version: i32,
 }
 
 bitflags! {
-    pub struct Flags: u8 {
+    /// The flags of a vertex, triangle, group or joint. Triangles store 16 bits
+    /// of flags and the other elements 8; only the low byte is used.
+    pub struct Flags: u16 {
         const SELECTED = 1;
         const HIDDEN = 2;
         const SELECTED2 = 4;
@@ -45,7 +220,14 @@ bitflags! {
     }
 }
 
-#[derive(Clone, Debug)]
+impl Flags {
+    /// Create flags from bits, keeping any unknown bits.
+    pub(crate) fn from_raw_bits(bits: u16) -> Self {
+        Flags { bits }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Vertex {
     pub flags: Flags,
     pub vertex: [f32; 3],
@@ -59,7 +241,7 @@ impl Vertex {
     };
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Triangle {
     pub flags: Flags,
     pub vertex_indices: [u16; 3],
@@ -76,10 +258,13 @@ impl Triangle {
     };
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Group {
     pub flags: Flags,
     pub name: String,
+    /// The name as stored in the file. This is written back instead of `name`
+    /// as long as `name` is unchanged.
+    pub raw_name: Vec<u8>,
     pub triangle_indices: Vec<u16>,
     pub material_index: i8,
 }
@@ -90,9 +275,11 @@ impl Group {
     };
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Material {
     pub name: String,
+    /// The name as stored in the file.
+    pub raw_name: Vec<u8>,
     pub ambient: [f32; 4],
     pub diffuse: [f32; 4],
     pub specular: [f32; 4],
@@ -101,33 +288,41 @@ pub struct Material {
     pub transparency: f32,
     pub mode: u8,
     pub texture: PathBuf,
+    /// The texture path as stored in the file.
+    pub raw_texture: Vec<u8>,
     pub alphamap: PathBuf,
+    /// The alpha map path as stored in the file.
+    pub raw_alphamap: Vec<u8>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct KeyFrameData {
     pub animation_fps: f32,
     pub current_time: f32,
     pub total_frames: i32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct KeyFrameRot {
     pub time: f32,
     pub rotation: [f32; 3],
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct KeyFramePos {
     pub time: f32,
     pub position: [f32; 3],
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Joint {
     pub flags: Flags,
     pub name: String,
+    /// The name as stored in the file.
+    pub raw_name: Vec<u8>,
     pub parent_name: String,
+    /// The parent name as stored in the file.
+    pub raw_parent_name: Vec<u8>,
     pub rotation: [f32; 3],
     pub position: [f32; 3],
     pub key_frames_rot: Vec<KeyFrameRot>,
@@ -140,13 +335,15 @@ impl Joint {
     };
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Comment {
     pub index: i32,
     pub comment: String,
+    /// The comment as stored in the file.
+    pub raw_comment: Vec<u8>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Comments {
     pub sub_version: i32,
     pub group_comments: Vec<Comment>,
@@ -155,51 +352,97 @@ pub struct Comments {
     pub model_comment: Option<Comment>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum VertexExInfo {
     SubVersion1(Vec<VertexEx1>),
     SubVersion2(Vec<VertexEx2>),
     SubVersion3(Vec<VertexEx3>),
 }
 
-#[derive(Clone, Debug)]
+impl VertexExInfo {
+    /// The number of vertices with extra info.
+    pub fn len(&self) -> usize {
+        match *self {
+            VertexExInfo::SubVersion1(ref vertex_ex) => vertex_ex.len(),
+            VertexExInfo::SubVersion2(ref vertex_ex) => vertex_ex.len(),
+            VertexExInfo::SubVersion3(ref vertex_ex) => vertex_ex.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The extra bone ids and their weights for the vertex at `index`.
+    pub fn bones(&self, index: usize) -> Option<([i8; 3], [u8; 3])> {
+        match *self {
+            VertexExInfo::SubVersion1(ref vertex_ex) => {
+                vertex_ex.get(index).map(|v| (v.bone_ids, v.weights))
+            }
+            VertexExInfo::SubVersion2(ref vertex_ex) => {
+                vertex_ex.get(index).map(|v| (v.bone_ids, v.weights))
+            }
+            VertexExInfo::SubVersion3(ref vertex_ex) => {
+                vertex_ex.get(index).map(|v| (v.bone_ids, v.weights))
+            }
+        }
+    }
+
+    /// Mutable references to the extra bone ids and their weights for the
+    /// vertex at `index`.
+    pub fn bones_mut(&mut self, index: usize) -> Option<(&mut [i8; 3], &mut [u8; 3])> {
+        match *self {
+            VertexExInfo::SubVersion1(ref mut vertex_ex) => vertex_ex
+                .get_mut(index)
+                .map(|v| (&mut v.bone_ids, &mut v.weights)),
+            VertexExInfo::SubVersion2(ref mut vertex_ex) => vertex_ex
+                .get_mut(index)
+                .map(|v| (&mut v.bone_ids, &mut v.weights)),
+            VertexExInfo::SubVersion3(ref mut vertex_ex) => vertex_ex
+                .get_mut(index)
+                .map(|v| (&mut v.bone_ids, &mut v.weights)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct VertexEx1 {
     pub bone_ids: [i8; 3],
     pub weights: [u8; 3],
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct VertexEx2 {
     pub bone_ids: [i8; 3],
     pub weights: [u8; 3],
     pub extra: u32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct VertexEx3 {
     pub bone_ids: [i8; 3],
     pub weights: [u8; 3],
     pub extra: [u32; 2],
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct JointExInfo {
     pub sub_version: i32,
     pub joint_ex: Vec<JointEx>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct JointEx {
     pub color: [f32; 3],
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ModelExInfo {
     pub sub_version: i32,
     pub model_ex: ModelEx,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ModelEx {
     pub joint_size: f32,
     pub transparency_mode: i32,
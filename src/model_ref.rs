@@ -0,0 +1,846 @@
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::slice::ChunksExact;
+
+use super::de::{self, Le};
+use super::model::*;
+use super::read::{BufReadExact, SliceReader};
+use super::{
+    trim_nul, ParseOptions, Reader, Result, Section, TextEncoding, Warning, GROUP_MIN_SIZE,
+};
+
+/// A borrowed view of an ms3d model file stored in a slice of bytes.
+///
+/// The layout of the file is validated once when the view is created, after
+/// which elements are decoded lazily from the slice as they are accessed.
+/// Names and paths are exposed as raw bytes, and only decoded on request.
+#[derive(Clone, Debug)]
+pub struct ModelRef<'a> {
+    bytes: &'a [u8],
+    options: ParseOptions,
+    header: Header,
+    vertices: Span,
+    triangles: Span,
+    groups: Span,
+    materials: Span,
+    key_frame_data: KeyFrameData,
+    joints: Span,
+    comments: Option<CommentSpans>,
+    vertex_ex: Option<(i32, Span)>,
+    joint_ex: Option<Span>,
+    model_ex_info: Option<ModelExInfo>,
+}
+
+/// The offset of the first element of a section and the number of elements.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct Span {
+    pub offset: usize,
+    pub len: usize,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct CommentSpans {
+    pub sub_version: i32,
+    pub group_comments: Span,
+    pub material_comments: Span,
+    pub joint_comments: Span,
+    pub model_comment: Span,
+}
+
+impl<'a> ModelRef<'a> {
+    /// Validate an ms3d model file stored in a slice of bytes.
+    pub fn new(bytes: &'a [u8]) -> Result<Self> {
+        Reader::from_slice(bytes, ParseOptions::default()).read_model_ref(bytes)
+    }
+
+    /// Validate an ms3d model file stored in a slice of bytes with the given
+    /// options, returning any warnings for problems that were ignored.
+    pub fn new_with(bytes: &'a [u8], options: ParseOptions) -> Result<(Self, Vec<Warning>)> {
+        let mut rdr = Reader::from_slice(bytes, options);
+        let model = rdr.read_model_ref(bytes)?;
+        Ok((model, rdr.warnings))
+    }
+
+    /// Decode the whole model into an owned `Model`.
+    pub fn to_model(&self) -> Result<Model> {
+        Reader::from_slice(self.bytes, self.options.clone()).read_model()
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn vertices(&self) -> Records<'a, Vertex> {
+        self.records(self.vertices)
+    }
+
+    pub fn triangles(&self) -> Records<'a, Triangle> {
+        self.records(self.triangles)
+    }
+
+    pub fn groups(&self) -> Groups<'a> {
+        Groups {
+            bytes: &self.bytes[self.groups.offset..],
+            len: self.groups.len,
+            encoding: self.options.encoding,
+        }
+    }
+
+    pub fn materials(&self) -> Records<'a, MaterialRef<'a>> {
+        self.records(self.materials)
+    }
+
+    pub fn key_frame_data(&self) -> &KeyFrameData {
+        &self.key_frame_data
+    }
+
+    pub fn joints(&self) -> Joints<'a> {
+        Joints {
+            bytes: &self.bytes[self.joints.offset..],
+            len: self.joints.len,
+            encoding: self.options.encoding,
+        }
+    }
+
+    /// The comments section, if present.
+    pub fn comments(&self) -> Option<CommentsRef<'a>> {
+        self.comments.map(|spans| CommentsRef {
+            bytes: self.bytes,
+            spans,
+            encoding: self.options.encoding,
+        })
+    }
+
+    /// The vertex extra info section, if present.
+    pub fn vertex_ex_info(&self) -> Option<VertexExInfoRef<'a>> {
+        self.vertex_ex.map(|(sub_version, span)| match sub_version {
+            1 => VertexExInfoRef::SubVersion1(self.records(span)),
+            2 => VertexExInfoRef::SubVersion2(self.records(span)),
+            _ => VertexExInfoRef::SubVersion3(self.records(span)),
+        })
+    }
+
+    /// The joint extra info section, if present.
+    pub fn joint_ex(&self) -> Option<Records<'a, JointEx>> {
+        self.joint_ex.map(|span| self.records(span))
+    }
+
+    /// The model extra info section, if present.
+    pub fn model_ex_info(&self) -> Option<&ModelExInfo> {
+        self.model_ex_info.as_ref()
+    }
+
+    fn records<T: Record<'a>>(&self, span: Span) -> Records<'a, T> {
+        Records::new(&self.bytes[span.offset..], span.len, self.options.encoding)
+    }
+}
+
+/// A fixed size element which can be decoded from a slice of bytes.
+pub trait Record<'a>: Sized {
+    const SIZE: usize;
+
+    fn decode(bytes: &'a [u8], encoding: &'static dyn TextEncoding) -> Self;
+}
+
+/// An iterator over fixed size elements of a model, decoded lazily.
+#[derive(Clone, Debug)]
+pub struct Records<'a, T> {
+    chunks: ChunksExact<'a, u8>,
+    encoding: &'static dyn TextEncoding,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T: Record<'a>> Records<'a, T> {
+    fn new(bytes: &'a [u8], len: usize, encoding: &'static dyn TextEncoding) -> Self {
+        Records {
+            chunks: bytes[..len * T::SIZE].chunks_exact(T::SIZE),
+            encoding,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Record<'a>> Iterator for Records<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let encoding = self.encoding;
+        self.chunks.next().map(|bytes| T::decode(bytes, encoding))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<T> {
+        let encoding = self.encoding;
+        self.chunks.nth(n).map(|bytes| T::decode(bytes, encoding))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl<'a, T: Record<'a>> DoubleEndedIterator for Records<'a, T> {
+    fn next_back(&mut self) -> Option<T> {
+        let encoding = self.encoding;
+        self.chunks
+            .next_back()
+            .map(|bytes| T::decode(bytes, encoding))
+    }
+}
+
+impl<'a, T: Record<'a>> ExactSizeIterator for Records<'a, T> {}
+
+impl<'a, T: Record<'a>> FusedIterator for Records<'a, T> {}
+
+impl<'a> Record<'a> for Vertex {
+    const SIZE: usize = de::Vertex::SIZE;
+
+    fn decode(bytes: &'a [u8], _: &'static dyn TextEncoding) -> Self {
+        let de::Vertex {
+            flags,
+            vertex,
+            bone_id,
+            reference_count,
+        } = de::Vertex::read_le(bytes);
+        Vertex {
+            flags: Flags::from_raw_bits(u16::from(flags)),
+            vertex,
+            bone_id,
+            reference_count,
+        }
+    }
+}
+
+impl<'a> Record<'a> for Triangle {
+    const SIZE: usize = de::Triangle::SIZE;
+
+    fn decode(bytes: &'a [u8], _: &'static dyn TextEncoding) -> Self {
+        let de::Triangle {
+            flags,
+            vertex_indices,
+            vertex_normals,
+            s,
+            t,
+            smoothing_group,
+            group_index,
+        } = de::Triangle::read_le(bytes);
+        Triangle {
+            flags: Flags::from_raw_bits(flags),
+            vertex_indices,
+            vertex_normals,
+            s,
+            t,
+            smoothing_group,
+            group_index,
+        }
+    }
+}
+
+impl<'a> Record<'a> for u16 {
+    const SIZE: usize = 2;
+
+    fn decode(bytes: &'a [u8], _: &'static dyn TextEncoding) -> Self {
+        u16::read_le(bytes)
+    }
+}
+
+impl<'a> Record<'a> for KeyFrameRot {
+    const SIZE: usize = de::KeyFrameRot::SIZE;
+
+    fn decode(bytes: &'a [u8], _: &'static dyn TextEncoding) -> Self {
+        let de::KeyFrameRot { time, rotation } = de::KeyFrameRot::read_le(bytes);
+        KeyFrameRot { time, rotation }
+    }
+}
+
+impl<'a> Record<'a> for KeyFramePos {
+    const SIZE: usize = de::KeyFramePos::SIZE;
+
+    fn decode(bytes: &'a [u8], _: &'static dyn TextEncoding) -> Self {
+        let de::KeyFramePos { time, position } = de::KeyFramePos::read_le(bytes);
+        KeyFramePos { time, position }
+    }
+}
+
+impl<'a> Record<'a> for VertexEx1 {
+    const SIZE: usize = de::VertexEx1::SIZE;
+
+    fn decode(bytes: &'a [u8], _: &'static dyn TextEncoding) -> Self {
+        let de::VertexEx1 { bone_ids, weights } = de::VertexEx1::read_le(bytes);
+        VertexEx1 { bone_ids, weights }
+    }
+}
+
+impl<'a> Record<'a> for VertexEx2 {
+    const SIZE: usize = de::VertexEx2::SIZE;
+
+    fn decode(bytes: &'a [u8], _: &'static dyn TextEncoding) -> Self {
+        let de::VertexEx2 {
+            bone_ids,
+            weights,
+            extra,
+        } = de::VertexEx2::read_le(bytes);
+        VertexEx2 {
+            bone_ids,
+            weights,
+            extra,
+        }
+    }
+}
+
+impl<'a> Record<'a> for VertexEx3 {
+    const SIZE: usize = de::VertexEx3::SIZE;
+
+    fn decode(bytes: &'a [u8], _: &'static dyn TextEncoding) -> Self {
+        let de::VertexEx3 {
+            bone_ids,
+            weights,
+            extra,
+        } = de::VertexEx3::read_le(bytes);
+        VertexEx3 {
+            bone_ids,
+            weights,
+            extra,
+        }
+    }
+}
+
+impl<'a> Record<'a> for JointEx {
+    const SIZE: usize = de::JointEx::SIZE;
+
+    fn decode(bytes: &'a [u8], _: &'static dyn TextEncoding) -> Self {
+        let de::JointEx { color } = de::JointEx::read_le(bytes);
+        JointEx { color }
+    }
+}
+
+/// A borrowed material.
+#[derive(Clone, Debug)]
+pub struct MaterialRef<'a> {
+    raw: de::Material,
+    bytes: &'a [u8],
+    encoding: &'static dyn TextEncoding,
+}
+
+impl<'a> Record<'a> for MaterialRef<'a> {
+    const SIZE: usize = de::Material::SIZE;
+
+    fn decode(bytes: &'a [u8], encoding: &'static dyn TextEncoding) -> Self {
+        MaterialRef {
+            raw: de::Material::read_le(bytes),
+            bytes,
+            encoding,
+        }
+    }
+}
+
+impl<'a> MaterialRef<'a> {
+    /// The name, up to the nul terminator.
+    pub fn name_bytes(&self) -> &'a [u8] {
+        trim_nul(&self.bytes[..32])
+    }
+
+    pub fn name(&self) -> String {
+        self.encoding.decode(self.name_bytes()).0
+    }
+
+    pub fn ambient(&self) -> [f32; 4] {
+        self.raw.ambient
+    }
+
+    pub fn diffuse(&self) -> [f32; 4] {
+        self.raw.diffuse
+    }
+
+    pub fn specular(&self) -> [f32; 4] {
+        self.raw.specular
+    }
+
+    pub fn emissive(&self) -> [f32; 4] {
+        self.raw.emissive
+    }
+
+    pub fn shininess(&self) -> f32 {
+        self.raw.shininess
+    }
+
+    pub fn transparency(&self) -> f32 {
+        self.raw.transparency
+    }
+
+    pub fn mode(&self) -> u8 {
+        self.raw.mode
+    }
+
+    /// The texture path, up to the nul terminator.
+    pub fn texture_bytes(&self) -> &'a [u8] {
+        trim_nul(&self.bytes[105..233])
+    }
+
+    /// The alpha map path, up to the nul terminator.
+    pub fn alphamap_bytes(&self) -> &'a [u8] {
+        trim_nul(&self.bytes[233..361])
+    }
+}
+
+/// An iterator over the groups of a model, decoded lazily.
+#[derive(Clone, Debug)]
+pub struct Groups<'a> {
+    bytes: &'a [u8],
+    len: usize,
+    encoding: &'static dyn TextEncoding,
+}
+
+impl<'a> Iterator for Groups<'a> {
+    type Item = GroupRef<'a>;
+
+    fn next(&mut self) -> Option<GroupRef<'a>> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+
+        let prefix = de::GroupPrefix::read_le(self.bytes);
+        let triangles_len = prefix.num_triangles as usize * 2;
+        let (bytes, rest) = self
+            .bytes
+            .split_at(de::GroupPrefix::SIZE + triangles_len + de::GroupSuffix::SIZE);
+        self.bytes = rest;
+        Some(GroupRef {
+            bytes,
+            prefix,
+            encoding: self.encoding,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a> ExactSizeIterator for Groups<'a> {}
+
+impl<'a> FusedIterator for Groups<'a> {}
+
+/// A borrowed group.
+#[derive(Clone, Debug)]
+pub struct GroupRef<'a> {
+    bytes: &'a [u8],
+    prefix: de::GroupPrefix,
+    encoding: &'static dyn TextEncoding,
+}
+
+impl<'a> GroupRef<'a> {
+    pub fn flags(&self) -> Flags {
+        Flags::from_raw_bits(u16::from(self.prefix.flags))
+    }
+
+    /// The name, up to the nul terminator.
+    pub fn name_bytes(&self) -> &'a [u8] {
+        trim_nul(&self.bytes[1..33])
+    }
+
+    pub fn name(&self) -> String {
+        self.encoding.decode(self.name_bytes()).0
+    }
+
+    pub fn triangle_indices(&self) -> Records<'a, u16> {
+        Records::new(
+            &self.bytes[de::GroupPrefix::SIZE..],
+            self.prefix.num_triangles as usize,
+            self.encoding,
+        )
+    }
+
+    pub fn material_index(&self) -> i8 {
+        self.bytes[self.bytes.len() - 1] as i8
+    }
+}
+
+/// An iterator over the joints of a model, decoded lazily.
+#[derive(Clone, Debug)]
+pub struct Joints<'a> {
+    bytes: &'a [u8],
+    len: usize,
+    encoding: &'static dyn TextEncoding,
+}
+
+impl<'a> Iterator for Joints<'a> {
+    type Item = JointRef<'a>;
+
+    fn next(&mut self) -> Option<JointRef<'a>> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+
+        let prefix = de::JointPrefix::read_le(self.bytes);
+        let key_frames_len = (prefix.num_key_frames_rot as usize * de::KeyFrameRot::SIZE)
+            + (prefix.num_key_frames_trans as usize * de::KeyFramePos::SIZE);
+        let (bytes, rest) = self.bytes.split_at(de::JointPrefix::SIZE + key_frames_len);
+        self.bytes = rest;
+        Some(JointRef {
+            bytes,
+            prefix,
+            encoding: self.encoding,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a> ExactSizeIterator for Joints<'a> {}
+
+impl<'a> FusedIterator for Joints<'a> {}
+
+/// A borrowed joint.
+#[derive(Clone, Debug)]
+pub struct JointRef<'a> {
+    bytes: &'a [u8],
+    prefix: de::JointPrefix,
+    encoding: &'static dyn TextEncoding,
+}
+
+impl<'a> JointRef<'a> {
+    pub fn flags(&self) -> Flags {
+        Flags::from_raw_bits(u16::from(self.prefix.flags))
+    }
+
+    /// The name, up to the nul terminator.
+    pub fn name_bytes(&self) -> &'a [u8] {
+        trim_nul(&self.bytes[1..33])
+    }
+
+    pub fn name(&self) -> String {
+        self.encoding.decode(self.name_bytes()).0
+    }
+
+    /// The name of the parent joint, up to the nul terminator.
+    pub fn parent_name_bytes(&self) -> &'a [u8] {
+        trim_nul(&self.bytes[33..65])
+    }
+
+    pub fn parent_name(&self) -> String {
+        self.encoding.decode(self.parent_name_bytes()).0
+    }
+
+    pub fn rotation(&self) -> [f32; 3] {
+        self.prefix.rotation
+    }
+
+    pub fn position(&self) -> [f32; 3] {
+        self.prefix.position
+    }
+
+    pub fn key_frames_rot(&self) -> Records<'a, KeyFrameRot> {
+        Records::new(
+            &self.bytes[de::JointPrefix::SIZE..],
+            self.prefix.num_key_frames_rot as usize,
+            self.encoding,
+        )
+    }
+
+    pub fn key_frames_trans(&self) -> Records<'a, KeyFramePos> {
+        let offset =
+            de::JointPrefix::SIZE + self.prefix.num_key_frames_rot as usize * de::KeyFrameRot::SIZE;
+        Records::new(
+            &self.bytes[offset..],
+            self.prefix.num_key_frames_trans as usize,
+            self.encoding,
+        )
+    }
+}
+
+/// The borrowed comments section of a model.
+#[derive(Clone, Debug)]
+pub struct CommentsRef<'a> {
+    bytes: &'a [u8],
+    spans: CommentSpans,
+    encoding: &'static dyn TextEncoding,
+}
+
+impl<'a> CommentsRef<'a> {
+    pub fn sub_version(&self) -> i32 {
+        self.spans.sub_version
+    }
+
+    pub fn group_comments(&self) -> CommentIter<'a> {
+        self.comments(self.spans.group_comments)
+    }
+
+    pub fn material_comments(&self) -> CommentIter<'a> {
+        self.comments(self.spans.material_comments)
+    }
+
+    pub fn joint_comments(&self) -> CommentIter<'a> {
+        self.comments(self.spans.joint_comments)
+    }
+
+    pub fn model_comment(&self) -> Option<CommentRef<'a>> {
+        self.comments(self.spans.model_comment).next()
+    }
+
+    fn comments(&self, span: Span) -> CommentIter<'a> {
+        CommentIter {
+            bytes: &self.bytes[span.offset..],
+            len: span.len,
+            encoding: self.encoding,
+        }
+    }
+}
+
+/// An iterator over comments, decoded lazily.
+#[derive(Clone, Debug)]
+pub struct CommentIter<'a> {
+    bytes: &'a [u8],
+    len: usize,
+    encoding: &'static dyn TextEncoding,
+}
+
+impl<'a> Iterator for CommentIter<'a> {
+    type Item = CommentRef<'a>;
+
+    fn next(&mut self) -> Option<CommentRef<'a>> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+
+        let de::CommentPrefix {
+            index,
+            comment_length,
+        } = de::CommentPrefix::read_le(self.bytes);
+        let (comment, rest) =
+            self.bytes[de::CommentPrefix::SIZE..].split_at(comment_length as usize);
+        self.bytes = rest;
+        Some(CommentRef {
+            index,
+            comment,
+            encoding: self.encoding,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a> ExactSizeIterator for CommentIter<'a> {}
+
+impl<'a> FusedIterator for CommentIter<'a> {}
+
+/// A borrowed comment.
+#[derive(Clone, Debug)]
+pub struct CommentRef<'a> {
+    index: i32,
+    comment: &'a [u8],
+    encoding: &'static dyn TextEncoding,
+}
+
+impl<'a> CommentRef<'a> {
+    pub fn index(&self) -> i32 {
+        self.index
+    }
+
+    pub fn comment_bytes(&self) -> &'a [u8] {
+        self.comment
+    }
+
+    pub fn comment(&self) -> String {
+        self.encoding.decode(self.comment).0
+    }
+}
+
+/// The borrowed vertex extra info section of a model.
+#[derive(Clone, Debug)]
+pub enum VertexExInfoRef<'a> {
+    SubVersion1(Records<'a, VertexEx1>),
+    SubVersion2(Records<'a, VertexEx2>),
+    SubVersion3(Records<'a, VertexEx3>),
+}
+
+impl<'a> Reader<SliceReader<'a>> {
+    /// Walk the sections of the model, validating each element and recording
+    /// where each section starts.
+    fn read_model_ref(&mut self, bytes: &'a [u8]) -> Result<ModelRef<'a>> {
+        let header = self.read_header()?;
+
+        self.begin_section(Section::Vertices);
+        let vertices = self.read_span()?;
+        self.for_each_element(vertices.len, de::Vertex::SIZE, |this| {
+            this.read_vertex().map(drop)
+        })?;
+
+        self.begin_section(Section::Triangles);
+        let triangles = self.read_span()?;
+        self.for_each_element(triangles.len, de::Triangle::SIZE, |this| {
+            this.read_triangle().map(drop)
+        })?;
+
+        self.begin_section(Section::Groups);
+        let groups = self.read_span()?;
+        self.for_each_element(groups.len, GROUP_MIN_SIZE, |this| {
+            this.skip_group().map(drop)
+        })?;
+
+        self.begin_section(Section::Materials);
+        let materials = self.read_span()?;
+        self.for_each_element(materials.len, de::Material::SIZE, |this| {
+            this.skip_material()
+        })?;
+
+        let key_frame_data = self.read_key_frame_data()?;
+
+        self.begin_section(Section::Joints);
+        let joints = self.read_span()?;
+        self.for_each_element(joints.len, de::JointPrefix::SIZE, |this| {
+            this.skip_joint().map(drop)
+        })?;
+
+        let comments = self.read_optional(Self::skip_comments)?;
+        let vertex_ex = self.read_optional(|this| this.skip_vertex_ex_info(vertices.len))?;
+        let joint_ex = self.read_optional(|this| this.skip_joint_ex_info(joints.len))?;
+        let model_ex_info = self.read_optional(Self::read_model_ex_info)?;
+
+        Ok(ModelRef {
+            bytes,
+            options: self.options.clone(),
+            header,
+            vertices,
+            triangles,
+            groups,
+            materials,
+            key_frame_data,
+            joints,
+            comments,
+            vertex_ex,
+            joint_ex,
+            model_ex_info,
+        })
+    }
+}
+
+impl<R: BufReadExact> Reader<R> {
+    /// Read the length of a section, returning the span of its elements.
+    pub(crate) fn read_span(&mut self) -> Result<Span> {
+        let len = self.read_u16()? as usize;
+        Ok(Span {
+            offset: self.rdr.position() as usize,
+            len,
+        })
+    }
+
+    /// Validate a group without reading its triangle indices, returning its
+    /// raw name.
+    pub(crate) fn skip_group(&mut self) -> Result<[u8; 32]> {
+        let de::GroupPrefix {
+            flags,
+            name,
+            num_triangles,
+        } = self.read_type()?;
+        self.convert_flags(flags, Group::ALLOWED_FLAGS)?;
+        self.check_string(&name)?;
+        self.skip_bytes(num_triangles as usize * 2)?;
+        self.read_type::<de::GroupSuffix>()?;
+        Ok(name)
+    }
+
+    /// Validate a material without decoding its name and paths.
+    fn skip_material(&mut self) -> Result<()> {
+        let de::Material {
+            name,
+            texture,
+            alphamap,
+            ..
+        } = self.read_type()?;
+        self.check_string(&name)?;
+        self.check_string(&texture)?;
+        self.check_string(&alphamap)
+    }
+
+    /// Validate a joint without reading its key frames, returning its raw name.
+    pub(crate) fn skip_joint(&mut self) -> Result<[u8; 32]> {
+        let de::JointPrefix {
+            flags,
+            name,
+            parent_name,
+            num_key_frames_rot,
+            num_key_frames_trans,
+            ..
+        } = self.read_type()?;
+        self.convert_flags(flags, Joint::ALLOWED_FLAGS)?;
+        self.check_string(&name)?;
+        self.check_string(&parent_name)?;
+        self.skip_bytes(num_key_frames_rot as usize * de::KeyFrameRot::SIZE)?;
+        self.skip_bytes(num_key_frames_trans as usize * de::KeyFramePos::SIZE)?;
+        Ok(name)
+    }
+
+    pub(crate) fn skip_comments(&mut self) -> Result<Option<CommentSpans>> {
+        self.begin_section(Section::Comments);
+        let sub_version = match self.read_sub_version(&[1])? {
+            Some(sub_version) => sub_version,
+            None => return Ok(None),
+        };
+        let mut total = 0;
+        let len = self.read_comment_count(&mut total)?;
+        let group_comments = self.skip_comment_list(len)?;
+        let len = self.read_comment_count(&mut total)?;
+        let material_comments = self.skip_comment_list(len)?;
+        let len = self.read_comment_count(&mut total)?;
+        let joint_comments = self.skip_comment_list(len)?;
+        let len = self.read_model_comment_count(&mut total)?;
+        let model_comment = self.skip_comment_list(len)?;
+
+        Ok(Some(CommentSpans {
+            sub_version,
+            group_comments,
+            material_comments,
+            joint_comments,
+            model_comment,
+        }))
+    }
+
+    fn skip_comment_list(&mut self, len: usize) -> Result<Span> {
+        let offset = self.rdr.position() as usize;
+        self.for_each_element(len, de::CommentPrefix::SIZE, Self::skip_comment)?;
+        Ok(Span { offset, len })
+    }
+
+    /// Check the length and text of a comment without decoding it.
+    fn skip_comment(&mut self) -> Result<()> {
+        let (_, len) = self.read_comment_prefix()?;
+        let offset = self.rdr.position();
+        if self.options.encoding.is_valid(self.read_bytes(len)?) {
+            return Ok(());
+        }
+        self.invalid_text(offset)
+    }
+
+    pub(crate) fn skip_vertex_ex_info(&mut self, len: usize) -> Result<Option<(i32, Span)>> {
+        self.begin_section(Section::VertexExInfo);
+        let sub_version = match self.read_sub_version(&[1, 2, 3])? {
+            Some(sub_version) => sub_version,
+            None => return Ok(None),
+        };
+        let size = match sub_version {
+            1 => de::VertexEx1::SIZE,
+            2 => de::VertexEx2::SIZE,
+            _ => de::VertexEx3::SIZE,
+        };
+        let offset = self.rdr.position() as usize;
+        self.skip_bytes(len * size)?;
+        Ok(Some((sub_version, Span { offset, len })))
+    }
+
+    pub(crate) fn skip_joint_ex_info(&mut self, len: usize) -> Result<Option<Span>> {
+        self.begin_section(Section::JointExInfo);
+        if self.read_sub_version(&[1])?.is_none() {
+            return Ok(None);
+        }
+        let offset = self.rdr.position() as usize;
+        self.skip_bytes(len * de::JointEx::SIZE)?;
+        Ok(Some(Span { offset, len }))
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use super::Model;
+
+/// How the face normals of the triangles around a vertex are weighted when
+/// they are averaged.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NormalWeighting {
+    /// Weight each face by its area, so large triangles dominate.
+    Area,
+    /// Weight each face by the angle of its corner at the vertex, which does
+    /// not depend on how the surface is tessellated.
+    Angle,
+}
+
+/// Options controlling how
+/// [`Model::recompute_normals`](struct.Model.html#method.recompute_normals)
+/// averages face normals.
+#[derive(Clone, Debug)]
+pub struct NormalOptions {
+    /// Defaults to [`Angle`](enum.NormalWeighting.html#variant.Angle).
+    pub weighting: NormalWeighting,
+}
+
+impl Default for NormalOptions {
+    fn default() -> Self {
+        NormalOptions {
+            weighting: NormalWeighting::Angle,
+        }
+    }
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(u: [f32; 3], v: [f32; 3]) -> [f32; 3] {
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+fn dot(u: [f32; 3], v: [f32; 3]) -> f32 {
+    u[0] * v[0] + u[1] * v[1] + u[2] * v[2]
+}
+
+/// Scale a vector to unit length, returning `None` if it has no length.
+fn normalize(v: [f32; 3]) -> Option<[f32; 3]> {
+    let len = dot(v, v).sqrt();
+    if len > 0.0 {
+        Some([v[0] / len, v[1] / len, v[2] / len])
+    } else {
+        None
+    }
+}
+
+/// The angle between two vectors, or 0 if either has no length.
+fn angle(u: [f32; 3], v: [f32; 3]) -> f32 {
+    match (normalize(u), normalize(v)) {
+        (Some(u), Some(v)) => dot(u, v).clamp(-1.0, 1.0).acos(),
+        _ => 0.0,
+    }
+}
+
+impl Model {
+    /// Recompute the normal of every triangle corner from the positions of
+    /// the vertices.
+    ///
+    /// The normal at a corner is the weighted average of the face normals of
+    /// the triangles which share its vertex and are in the same smoothing
+    /// group. Triangles in smoothing group 0 are flat shaded, as in
+    /// MilkShape. Triangles which refer to missing vertices are left
+    /// unchanged.
+    ///
+    /// A degenerate triangle adds nothing to the average. Its corners get the
+    /// normals averaged from the other triangles in its smoothing group which
+    /// share their vertices, and a zero normal where there are none, such as
+    /// in smoothing group 0.
+    pub fn recompute_normals(&mut self, options: &NormalOptions) {
+        let mut faces = Vec::with_capacity(self.triangles.len());
+        let mut sums: HashMap<(u16, u8), [f32; 3]> = HashMap::new();
+        for triangle in &self.triangles {
+            let corners = triangle.vertex_indices;
+            let positions = match (
+                self.vertices.get(corners[0] as usize),
+                self.vertices.get(corners[1] as usize),
+                self.vertices.get(corners[2] as usize),
+            ) {
+                (Some(a), Some(b), Some(c)) => [a.vertex, b.vertex, c.vertex],
+                _ => {
+                    faces.push(None);
+                    continue;
+                }
+            };
+            // The length of the cross product is twice the area.
+            let normal = cross(
+                sub(positions[1], positions[0]),
+                sub(positions[2], positions[0]),
+            );
+            let unit = normalize(normal).unwrap_or([0.0; 3]);
+            faces.push(Some(unit));
+            if triangle.smoothing_group == 0 {
+                continue;
+            }
+
+            for corner in 0..3 {
+                let weighted = match options.weighting {
+                    NormalWeighting::Area => normal,
+                    NormalWeighting::Angle => {
+                        let position = positions[corner];
+                        let angle = angle(
+                            sub(positions[(corner + 1) % 3], position),
+                            sub(positions[(corner + 2) % 3], position),
+                        );
+                        [unit[0] * angle, unit[1] * angle, unit[2] * angle]
+                    }
+                };
+                let sum = sums
+                    .entry((corners[corner], triangle.smoothing_group))
+                    .or_insert([0.0; 3]);
+                for axis in 0..3 {
+                    sum[axis] += weighted[axis];
+                }
+            }
+        }
+
+        for (triangle, face) in self.triangles.iter_mut().zip(faces) {
+            let face = match face {
+                Some(face) => face,
+                None => continue,
+            };
+            for corner in 0..3 {
+                triangle.vertex_normals[corner] = if triangle.smoothing_group == 0 {
+                    face
+                } else {
+                    let key = (triangle.vertex_indices[corner], triangle.smoothing_group);
+                    normalize(sums[&key]).unwrap_or(face)
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+
+use super::{Section, TextEncoding, Windows1252};
+
+/// Options controlling how an ms3d model file is parsed.
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    /// If true (the default), any invalid data in the file is an error. If
+    /// false, data written by non-conforming exporters is accepted and a
+    /// [`Warning`](struct.Warning.html) is reported instead:
+    ///
+    /// * Unknown flag bits are kept as-is.
+    /// * Names, paths and comments which are not valid in `encoding` are
+    ///   decoded lossily.
+    /// * An optional section with an unknown sub-version is skipped, along with
+    ///   any sections after it. The skipped data is kept in
+    ///   [`Model::unknown_trailer`](struct.Model.html#structfield.unknown_trailer).
+    pub strict: bool,
+    /// The encoding of names, paths and comments. Defaults to Windows-1252.
+    pub encoding: &'static dyn TextEncoding,
+    /// Limits on the size of the file, to bound the memory used when parsing
+    /// untrusted input.
+    pub limits: Limits,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            strict: true,
+            encoding: &Windows1252,
+            limits: Limits::default(),
+        }
+    }
+}
+
+/// Limits on the size of a model file.
+///
+/// Element counts are also checked against the size of the remaining input
+/// when it is known, so a slice or seekable reader never allocates more than
+/// the input could hold. Exceeding a limit is an
+/// [`Error::LimitExceeded`](enum.Error.html#variant.LimitExceeded).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum total number of comments. Defaults to 65536.
+    pub max_comments: usize,
+    /// The maximum length of a single comment in bytes. Defaults to 1 MiB.
+    pub max_comment_length: usize,
+    /// The maximum number of bytes read from the input. Defaults to 256 MiB.
+    pub max_total_bytes: u64,
+}
+
+impl Limits {
+    /// No limits other than the size of the input.
+    pub fn unlimited() -> Self {
+        Limits {
+            max_comments: usize::MAX,
+            max_comment_length: usize::MAX,
+            max_total_bytes: u64::MAX,
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_comments: 1 << 16,
+            max_comment_length: 1 << 20,
+            max_total_bytes: 1 << 28,
+        }
+    }
+}
+
+/// Options controlling how an ms3d model file is written.
+#[derive(Clone, Debug)]
+pub struct WriteOptions {
+    /// The encoding of names, paths and comments. Defaults to Windows-1252.
+    ///
+    /// The raw bytes of a name are written unchanged if they decode to the
+    /// current name in this encoding, so this should match the encoding the
+    /// model was read with.
+    pub encoding: &'static dyn TextEncoding,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            encoding: &Windows1252,
+        }
+    }
+}
+
+/// A problem found in a model file which was accepted because it was parsed in
+/// lenient mode.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Warning {
+    pub section: Section,
+    pub index: usize,
+    pub offset: u64,
+    pub kind: WarningKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WarningKind {
+    /// An element has flags which are not valid for its type.
+    InvalidFlags { bits: u16 },
+    /// A name, path or comment is not valid in the text encoding used.
+    InvalidText,
+    /// The section has an unsupported sub-version, so it and all later sections
+    /// were skipped.
+    UnsupportedSubVersion { sub_version: i32 },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            WarningKind::InvalidFlags { bits } => write!(
+                f,
+                "invalid flags {} for element {} of {} at offset {}",
+                bits, self.index, self.section, self.offset
+            ),
+            WarningKind::InvalidText => write!(
+                f,
+                "invalid text in element {} of {} at offset {}",
+                self.index, self.section, self.offset
+            ),
+            WarningKind::UnsupportedSubVersion { sub_version } => write!(
+                f,
+                "skipped {} with unsupported sub-version {} at offset {}",
+                self.section, sub_version, self.offset
+            ),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use super::layout::{layout_seek, FileLayout, SectionLayout};
+use super::model::{JointEx, Material};
+use super::write::Writer;
+use super::{Error, Result, Section, WriteOptions};
+
+/// Overwrites single records of an ms3d model file in place, without decoding
+/// or re-encoding the rest of the file.
+///
+/// Only fields with a fixed size in the file can be changed, so the layout of
+/// the file is never affected. Strings are checked against the size of their
+/// field, which is 32 bytes for names and 128 bytes for paths.
+pub struct Patcher<F> {
+    file: F,
+    start: u64,
+    layout: FileLayout,
+    options: WriteOptions,
+}
+
+impl<F: Read + Write + Seek> Patcher<F> {
+    /// Find the layout of the model file starting at the current position of
+    /// `file`.
+    pub fn new(file: F) -> Result<Self> {
+        Patcher::with_options(file, WriteOptions::default())
+    }
+
+    /// Find the layout of the model file starting at the current position of
+    /// `file`, with the given options for encoding strings.
+    pub fn with_options(mut file: F, options: WriteOptions) -> Result<Self> {
+        let start = file
+            .stream_position()
+            .map_err(|error| Error::Io { offset: 0, error })?;
+        let layout = layout_seek(&mut file)?;
+        Ok(Patcher {
+            file,
+            start,
+            layout,
+            options,
+        })
+    }
+
+    /// The layout of the file being patched.
+    pub fn layout(&self) -> &FileLayout {
+        &self.layout
+    }
+
+    /// Return the file, with every patch written so far.
+    pub fn into_inner(self) -> F {
+        self.file
+    }
+
+    /// Overwrite a material. Every field of a material has a fixed size, so
+    /// the whole record is replaced.
+    pub fn set_material(&mut self, index: usize, material: &Material) -> Result<()> {
+        let pos = record(&self.layout.materials, Section::Materials, index)?.start;
+        self.patch(Section::Materials, index, pos, |wtr| {
+            wtr.write_material(material)
+        })
+    }
+
+    /// Overwrite the name of a group, which is limited to 32 bytes.
+    pub fn set_group_name(&mut self, index: usize, name: &str) -> Result<()> {
+        // The name follows the one byte of flags.
+        let pos = record(&self.layout.groups, Section::Groups, index)?.start + 1;
+        self.patch_name(Section::Groups, index, pos, name)
+    }
+
+    /// Overwrite the name of a joint, which is limited to 32 bytes.
+    pub fn set_joint_name(&mut self, index: usize, name: &str) -> Result<()> {
+        let pos = self.joint(index)?.start + 1;
+        self.patch_name(Section::Joints, index, pos, name)
+    }
+
+    /// Overwrite the parent name of a joint, which is limited to 32 bytes.
+    pub fn set_joint_parent_name(&mut self, index: usize, parent_name: &str) -> Result<()> {
+        let pos = self.joint(index)?.start + 33;
+        self.patch_name(Section::Joints, index, pos, parent_name)
+    }
+
+    /// Overwrite the colour of a joint, which is stored in the joint extra
+    /// info section.
+    pub fn set_joint_color(&mut self, index: usize, color: [f32; 3]) -> Result<()> {
+        let pos = match self.layout.joint_ex_info {
+            Some(ref joint_ex_info) => record(joint_ex_info, Section::JointExInfo, index)?.start,
+            None => {
+                return Err(Error::MissingSection {
+                    section: Section::JointExInfo,
+                })
+            }
+        };
+        self.patch(Section::JointExInfo, index, pos, |wtr| {
+            wtr.write_joint_ex(&JointEx { color })
+        })
+    }
+
+    fn joint(&self, index: usize) -> Result<Range<u64>> {
+        let records = &self.layout.joints.records;
+        match records.get(index) {
+            Some(joint) => Ok(joint.range.clone()),
+            None => Err(Error::IndexOutOfRange {
+                section: Section::Joints,
+                index,
+                len: records.len(),
+            }),
+        }
+    }
+
+    fn patch_name(&mut self, section: Section, index: usize, pos: u64, name: &str) -> Result<()> {
+        self.patch(section, index, pos, |wtr| {
+            let name: [u8; 32] = wtr.convert_string(name, &[])?;
+            wtr.write_bytes(&name)
+        })
+    }
+
+    /// Seek to `pos` and write to the file with `f`.
+    fn patch<W>(&mut self, section: Section, index: usize, pos: u64, f: W) -> Result<()>
+    where
+        W: FnOnce(&mut Writer<&mut F>) -> Result<()>,
+    {
+        let io_error = |error| Error::Io { offset: pos, error };
+        self.file
+            .seek(SeekFrom::Start(self.start + pos))
+            .map_err(io_error)?;
+        let mut wtr = Writer::new(&mut self.file, self.options.clone());
+        wtr.begin_element(section, index, pos);
+        f(&mut wtr)?;
+        self.file.flush().map_err(io_error)
+    }
+}
+
+/// Find the range of a record in a section.
+fn record(layout: &SectionLayout, section: Section, index: usize) -> Result<Range<u64>> {
+    match layout.records.get(index) {
+        Some(range) => Ok(range.clone()),
+        None => Err(Error::IndexOutOfRange {
+            section,
+            index,
+            len: layout.records.len(),
+        }),
+    }
+}
@@ -0,0 +1,98 @@
+use std::io;
+
+use super::de::Le;
+use super::read::{BufReadExact, IoReader, SeekReader};
+use super::{de, trim_nul, Error, ParseOptions, Reader, Result, Section, GROUP_MIN_SIZE};
+
+/// A summary of the contents of a model file, read without decoding the bulk
+/// of the model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelSummary {
+    pub version: i32,
+    pub num_vertices: usize,
+    pub num_triangles: usize,
+    pub group_names: Vec<String>,
+    pub material_names: Vec<String>,
+    pub joint_names: Vec<String>,
+    pub total_frames: i32,
+    /// True if the file has a vertex extra info section, which holds the bone
+    /// weights of each vertex.
+    pub has_vertex_weights: bool,
+}
+
+/// Read a summary of an ms3d model file from a reader.
+///
+/// The sections of the file are walked the same way as
+/// [`Model::from_reader`](struct.Model.html#method.from_reader), but vertices,
+/// triangles, key frames and other bulk data are skipped over rather than
+/// decoded.
+pub fn probe<R: io::Read>(rdr: R) -> Result<ModelSummary> {
+    Reader::new(IoReader::new(rdr), ParseOptions::default()).read_summary()
+}
+
+/// Read a summary of an ms3d model file from a seekable reader.
+///
+/// This is the same as [`probe`](fn.probe.html), but bulk data is skipped by
+/// seeking past it instead of reading it.
+pub fn probe_seek<R: io::Read + io::Seek>(rdr: R) -> Result<ModelSummary> {
+    let rdr = SeekReader::new(rdr).map_err(|error| Error::Io { offset: 0, error })?;
+    Reader::new(rdr, ParseOptions::default()).read_summary()
+}
+
+impl<R: BufReadExact> Reader<R> {
+    fn read_summary(&mut self) -> Result<ModelSummary> {
+        let header = self.read_header()?;
+
+        self.begin_section(Section::Vertices);
+        let num_vertices = self.read_u16()? as usize;
+        self.skip_bytes(num_vertices * de::Vertex::SIZE)?;
+
+        self.begin_section(Section::Triangles);
+        let num_triangles = self.read_u16()? as usize;
+        self.skip_bytes(num_triangles * de::Triangle::SIZE)?;
+
+        self.begin_section(Section::Groups);
+        let len = self.read_u16()? as usize;
+        let group_names = self.read_elements(len, GROUP_MIN_SIZE, |this| {
+            let name = this.skip_group()?;
+            Ok(this.decode_name(&name))
+        })?;
+
+        self.begin_section(Section::Materials);
+        let len = self.read_u16()? as usize;
+        let material_names = self.read_elements(len, de::Material::SIZE, |this| {
+            let de::Material { name, .. } = this.read_type()?;
+            this.convert_string(&name)
+        })?;
+
+        let key_frame_data = self.read_key_frame_data()?;
+
+        self.begin_section(Section::Joints);
+        let len = self.read_u16()? as usize;
+        let joint_names = self.read_elements(len, de::JointPrefix::SIZE, |this| {
+            let name = this.skip_joint()?;
+            Ok(this.decode_name(&name))
+        })?;
+
+        self.read_optional(Self::skip_comments)?;
+        let vertex_ex = self.read_optional(|this| this.skip_vertex_ex_info(num_vertices))?;
+        self.read_optional(|this| this.skip_joint_ex_info(joint_names.len()))?;
+        self.read_optional(Self::read_model_ex_info)?;
+
+        Ok(ModelSummary {
+            version: header.version,
+            num_vertices,
+            num_triangles,
+            group_names,
+            material_names,
+            joint_names,
+            total_frames: key_frame_data.total_frames,
+            has_vertex_weights: vertex_ex.is_some(),
+        })
+    }
+
+    /// Decode a name which has already been checked.
+    fn decode_name(&self, bytes: &[u8]) -> String {
+        self.options.encoding.decode(trim_nul(bytes)).0
+    }
+}
@@ -1,35 +1,158 @@
-use std::io;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
 
 pub(crate) trait BufReadExact {
     fn buf_read_exact(&mut self, len: usize) -> io::Result<&[u8]>;
+
+    /// Discard the next `len` bytes of input.
+    fn skip(&mut self, len: usize) -> io::Result<()>;
+
+    /// Returns true if there is no more input to read.
+    fn at_eof(&mut self) -> io::Result<bool>;
+
+    /// The number of bytes consumed so far.
+    fn position(&self) -> u64;
+
+    /// Read the rest of the input, up to `limit` bytes, onto the end of `buf`.
+    fn read_to_end(&mut self, buf: &mut Vec<u8>, limit: u64) -> io::Result<()>;
+
+    /// The number of bytes left in the input, if known.
+    fn remaining(&self) -> Option<u64> {
+        None
+    }
 }
 
+/// A reader over any `io::Read`, buffered so that each element does not need
+/// a separate read call.
 pub(crate) struct IoReader<R: io::Read> {
-    rdr: R,
+    rdr: io::BufReader<R>,
+    /// Holds elements which do not fit in the remaining buffered input.
     buf: Vec<u8>,
+    /// The length of the last element returned directly from the buffered
+    /// input, which is consumed before the next read.
+    pending: usize,
+    pos: u64,
 }
 
 impl<R: io::Read> IoReader<R> {
     pub fn new(rdr: R) -> Self {
-        IoReader { rdr, buf: Vec::new() }
+        IoReader {
+            rdr: io::BufReader::new(rdr),
+            buf: Vec::new(),
+            pending: 0,
+            pos: 0,
+        }
+    }
+
+    fn consume_pending(&mut self) {
+        self.rdr.consume(self.pending);
+        self.pending = 0;
     }
 }
 
 impl<R: io::Read> BufReadExact for IoReader<R> {
     fn buf_read_exact(&mut self, len: usize) -> io::Result<&[u8]> {
+        self.consume_pending();
+        if self.rdr.buffer().is_empty() {
+            self.rdr.fill_buf()?;
+        }
+        if self.rdr.buffer().len() >= len {
+            self.pending = len;
+            self.pos += len as u64;
+            return Ok(&self.rdr.buffer()[..len]);
+        }
         self.buf.resize(len, 0);
-        self.rdr.read_exact(self.buf.as_mut_slice())?;
-        Ok(self.buf.as_slice())
+        self.rdr.read_exact(&mut self.buf)?;
+        self.pos += len as u64;
+        Ok(&self.buf)
+    }
+
+    fn skip(&mut self, len: usize) -> io::Result<()> {
+        self.consume_pending();
+        let len = len as u64;
+        let skipped = io::copy(&mut (&mut self.rdr).take(len), &mut io::sink())?;
+        self.pos += skipped;
+        if skipped < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    fn at_eof(&mut self) -> io::Result<bool> {
+        self.consume_pending();
+        Ok(self.rdr.fill_buf()?.is_empty())
+    }
+
+    fn position(&self) -> u64 {
+        self.pos
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>, limit: u64) -> io::Result<()> {
+        self.consume_pending();
+        let read = (&mut self.rdr).take(limit).read_to_end(buf)?;
+        self.pos += read as u64;
+        Ok(())
+    }
+}
+
+/// A reader which seeks past skipped input instead of reading it.
+pub(crate) struct SeekReader<R: io::Read + Seek> {
+    rdr: IoReader<R>,
+    /// The position of the end of the input, relative to where reading started.
+    end: u64,
+}
+
+impl<R: io::Read + Seek> SeekReader<R> {
+    pub fn new(mut rdr: R) -> io::Result<Self> {
+        let start = rdr.stream_position()?;
+        let end = rdr.seek(SeekFrom::End(0))?;
+        rdr.seek(SeekFrom::Start(start))?;
+        Ok(SeekReader {
+            rdr: IoReader::new(rdr),
+            end: end.saturating_sub(start),
+        })
+    }
+}
+
+impl<R: io::Read + Seek> BufReadExact for SeekReader<R> {
+    fn buf_read_exact(&mut self, len: usize) -> io::Result<&[u8]> {
+        self.rdr.buf_read_exact(len)
+    }
+
+    fn skip(&mut self, len: usize) -> io::Result<()> {
+        if self.rdr.pos + len as u64 > self.end {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.rdr.consume_pending();
+        self.rdr.rdr.seek_relative(len as i64)?;
+        self.rdr.pos += len as u64;
+        Ok(())
+    }
+
+    fn at_eof(&mut self) -> io::Result<bool> {
+        self.rdr.at_eof()
+    }
+
+    fn position(&self) -> u64 {
+        self.rdr.position()
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>, limit: u64) -> io::Result<()> {
+        self.rdr.read_to_end(buf, limit)
+    }
+
+    fn remaining(&self) -> Option<u64> {
+        Some(self.end.saturating_sub(self.rdr.position()))
     }
 }
 
 pub(crate) struct SliceReader<'a> {
     slice: &'a [u8],
+    pos: u64,
 }
 
 impl<'a> SliceReader<'a> {
     pub fn new(slice: &'a [u8]) -> Self {
-        SliceReader { slice }
+        SliceReader { slice, pos: 0 }
     }
 }
 
@@ -40,6 +163,29 @@ impl<'a> BufReadExact for SliceReader<'a> {
         }
         let (head, tail) = self.slice.split_at(len);
         self.slice = tail;
+        self.pos += len as u64;
         Ok(head)
     }
-}
\ No newline at end of file
+
+    fn skip(&mut self, len: usize) -> io::Result<()> {
+        self.buf_read_exact(len).map(drop)
+    }
+
+    fn at_eof(&mut self) -> io::Result<bool> {
+        Ok(self.slice.is_empty())
+    }
+
+    fn position(&self) -> u64 {
+        self.pos
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>, limit: u64) -> io::Result<()> {
+        let len = (self.slice.len() as u64).min(limit) as usize;
+        buf.extend_from_slice(self.buf_read_exact(len)?);
+        Ok(())
+    }
+
+    fn remaining(&self) -> Option<u64> {
+        Some(self.slice.len() as u64)
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use super::{Group, Model};
+
+/// A vertex with all of its attributes, for uploading to a GPU.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+    /// Up to four joints the vertex is attached to, or -1 for none. The first
+    /// is the vertex's own bone id, and the rest come from the vertex extra
+    /// info.
+    pub bone_ids: [i8; 4],
+    /// The weight of each joint in `bone_ids`, adding up to 1 unless the
+    /// vertex is not attached to any joint.
+    pub weights: [f32; 4],
+}
+
+/// The index buffer of a mesh, using 16-bit indices whenever there are few
+/// enough vertices.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    pub fn len(&self) -> usize {
+        match *self {
+            Indices::U16(ref indices) => indices.len(),
+            Indices::U32(ref indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The index at `index` in the buffer, widened to `u32`.
+    pub fn get(&self, index: usize) -> Option<u32> {
+        match *self {
+            Indices::U16(ref indices) => indices.get(index).map(|&i| u32::from(i)),
+            Indices::U32(ref indices) => indices.get(index).cloned(),
+        }
+    }
+}
+
+/// An indexed triangle list for one group of a model, returned by
+/// [`Model::to_render_meshes`](struct.Model.html#method.to_render_meshes).
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderMesh {
+    pub name: String,
+    /// The index of the group's material, or -1 for none.
+    pub material_index: i8,
+    pub vertices: Vec<RenderVertex>,
+    /// Three indices into `vertices` for each triangle.
+    pub indices: Indices,
+}
+
+/// The attributes which decide whether two triangle corners can share a
+/// vertex. Floats are compared by their bits, with negative zero treated as
+/// zero.
+#[derive(PartialEq, Eq, Hash)]
+struct CornerKey {
+    vertex: u16,
+    normal: [u32; 3],
+    uv: [u32; 2],
+}
+
+fn bits<const N: usize>(values: [f32; N]) -> [u32; N] {
+    let mut bits = [0; N];
+    for (bits, value) in bits.iter_mut().zip(&values) {
+        *bits = (value + 0.0).to_bits();
+    }
+    bits
+}
+
+impl Model {
+    /// Convert each group to an indexed triangle list with per-vertex
+    /// attributes, in the order of the groups.
+    ///
+    /// The model stores normals and texture coordinates for each triangle
+    /// corner, so a vertex is split into one render vertex for each distinct
+    /// normal and texture coordinate it is used with. Triangles which refer to
+    /// missing vertices, and indices to missing triangles, are skipped.
+    ///
+    /// Joint weights follow MilkShape: the extra info weights are percentages
+    /// for the first three joints, the fourth joint gets the rest, and a
+    /// vertex with no weights is attached to its own bone id only.
+    pub fn to_render_meshes(&self) -> Vec<RenderMesh> {
+        self.groups
+            .iter()
+            .map(|group| self.render_mesh(group))
+            .collect()
+    }
+
+    fn render_mesh(&self, group: &Group) -> RenderMesh {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut corners = HashMap::new();
+        for &index in &group.triangle_indices {
+            let triangle = match self.triangles.get(index as usize) {
+                Some(triangle) => triangle,
+                None => continue,
+            };
+            let in_range = |&index: &u16| (index as usize) < self.vertices.len();
+            if !triangle.vertex_indices.iter().all(in_range) {
+                continue;
+            }
+            for corner in 0..3 {
+                let vertex = triangle.vertex_indices[corner];
+                let normal = triangle.vertex_normals[corner];
+                let uv = [triangle.s[corner], triangle.t[corner]];
+                let key = CornerKey {
+                    vertex,
+                    normal: bits(normal),
+                    uv: bits(uv),
+                };
+                let next = vertices.len() as u32;
+                let index = *corners.entry(key).or_insert_with(|| {
+                    vertices.push(self.render_vertex(vertex as usize, normal, uv));
+                    next
+                });
+                indices.push(index);
+            }
+        }
+
+        let indices = if vertices.len() <= u16::MAX as usize + 1 {
+            Indices::U16(indices.into_iter().map(|index| index as u16).collect())
+        } else {
+            Indices::U32(indices)
+        };
+        RenderMesh {
+            name: group.name.clone(),
+            material_index: group.material_index,
+            vertices,
+            indices,
+        }
+    }
+
+    fn render_vertex(&self, index: usize, normal: [f32; 3], uv: [f32; 2]) -> RenderVertex {
+        let vertex = &self.vertices[index];
+        let mut bone_ids = [vertex.bone_id, -1, -1, -1];
+        let mut percentages = [100, 0, 0, 0];
+        let info = self.vertex_ex_info.as_ref();
+        if let Some((extra_ids, extra_weights)) = info.and_then(|info| info.bones(index)) {
+            bone_ids[1..].copy_from_slice(&extra_ids);
+            if extra_weights != [0; 3] {
+                let total: i32 = extra_weights.iter().map(|&w| i32::from(w)).sum();
+                percentages = [
+                    i32::from(extra_weights[0]),
+                    i32::from(extra_weights[1]),
+                    i32::from(extra_weights[2]),
+                    (100 - total).max(0),
+                ];
+            }
+        }
+
+        let mut weights = [0.0; 4];
+        for (slot, weight) in weights.iter_mut().enumerate() {
+            if bone_ids[slot] >= 0 {
+                *weight = percentages[slot] as f32;
+            }
+        }
+        let total: f32 = weights.iter().sum();
+        if total > 0.0 {
+            for weight in &mut weights {
+                *weight /= total;
+            }
+        }
+
+        RenderVertex {
+            position: vertex.vertex,
+            normal,
+            uv,
+            bone_ids,
+            weights,
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use super::edit::{index_map, remap_comments, retain_marked};
+use super::validate::is_optional_index;
+use super::{Model, Triangle, Vertex};
+
+/// Options controlling which defects
+/// [`Model::repair`](struct.Model.html#method.repair) fixes. All repairs
+/// except `remove_invalid_triangle_indices` are enabled by default.
+#[derive(Clone, Debug)]
+pub struct RepairOptions {
+    /// Remove indices to triangles that do not exist from the groups' lists of
+    /// triangles, keeping the groups themselves. This is off by default; when
+    /// enabled, it runs first and leaves `remove_invalid_groups` nothing to do.
+    pub remove_invalid_triangle_indices: bool,
+    /// Remove groups which refer to triangles that do not exist, along with
+    /// their comments. Triangles which belonged to a removed group are then
+    /// removed by `remove_invalid_triangles`.
+    pub remove_invalid_groups: bool,
+    /// Remove triangles which use the same vertex more than once, or whose
+    /// vertices are collinear.
+    pub remove_degenerate_triangles: bool,
+    /// Remove triangles which refer to vertices that do not exist, or which
+    /// are not in any group and have an invalid group index.
+    pub remove_invalid_triangles: bool,
+    /// Set invalid material indices and bone ids to -1.
+    pub clamp_indices: bool,
+    /// Make each triangle's group index match the group which lists it, and
+    /// make each triangle listed by exactly one group.
+    pub fix_group_indices: bool,
+    /// Set the reference count of each vertex to the number of triangle
+    /// corners which use it.
+    pub recompute_reference_counts: bool,
+}
+
+impl Default for RepairOptions {
+    fn default() -> Self {
+        RepairOptions {
+            remove_invalid_triangle_indices: false,
+            remove_invalid_groups: true,
+            remove_degenerate_triangles: true,
+            remove_invalid_triangles: true,
+            clamp_indices: true,
+            fix_group_indices: true,
+            recompute_reference_counts: true,
+        }
+    }
+}
+
+/// The changes made by [`Model::repair`](struct.Model.html#method.repair).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RepairReport {
+    pub removed_triangle_indices: usize,
+    pub removed_groups: usize,
+    /// The number of triangles removed because their group was removed.
+    pub removed_orphan_triangles: usize,
+    pub removed_degenerate_triangles: usize,
+    pub removed_invalid_triangles: usize,
+    pub clamped_material_indices: usize,
+    /// The number of bone ids clamped, in both vertices and vertex extra info.
+    pub clamped_bone_ids: usize,
+    /// The number of triangles whose group index was changed.
+    pub updated_group_indices: usize,
+    /// The number of triangles added to or removed from a group's list of
+    /// triangles.
+    pub updated_group_memberships: usize,
+    pub updated_reference_counts: usize,
+}
+
+impl RepairReport {
+    /// Returns true if the model was not changed.
+    pub fn is_empty(&self) -> bool {
+        *self == RepairReport::default()
+    }
+}
+
+/// The number of triangle corners which use each vertex.
+pub(crate) fn reference_counts(num_vertices: usize, triangles: &[Triangle]) -> Vec<u8> {
+    let mut counts = vec![0u8; num_vertices];
+    for triangle in triangles {
+        for &index in &triangle.vertex_indices {
+            if let Some(count) = counts.get_mut(index as usize) {
+                *count = count.saturating_add(1);
+            }
+        }
+    }
+    counts
+}
+
+fn is_degenerate(triangle: &Triangle, vertices: &[Vertex]) -> bool {
+    let [a, b, c] = triangle.vertex_indices;
+    if a == b || b == c || a == c {
+        return true;
+    }
+    let position = |index: u16| vertices.get(index as usize).map(|v| v.vertex);
+    let (a, b, c) = match (position(a), position(b), position(c)) {
+        (Some(a), Some(b), Some(c)) => (a, b, c),
+        _ => return false,
+    };
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let cross = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    cross == [0.0; 3]
+}
+
+impl Model {
+    /// Fix common inconsistencies between the elements of a model, returning a
+    /// report of what was changed.
+    ///
+    /// The repairs are applied in the order of the fields of
+    /// [`RepairOptions`](struct.RepairOptions.html). Removing groups and
+    /// triangles updates every index which refers to them.
+    pub fn repair(&mut self, options: &RepairOptions) -> RepairReport {
+        let mut report = RepairReport::default();
+        if options.remove_invalid_triangle_indices {
+            self.remove_invalid_triangle_indices(&mut report);
+        }
+        let mut orphans = vec![false; self.triangles.len()];
+        let group_map = if options.remove_invalid_groups {
+            self.remove_invalid_groups(&mut orphans, &mut report)
+        } else {
+            (0..self.groups.len()).map(Some).collect()
+        };
+        let mut owners = self.triangle_owners(&group_map);
+        for triangle in &mut self.triangles {
+            if let Some(&Some(index)) = group_map.get(triangle.group_index as usize) {
+                triangle.group_index = index as u8;
+            }
+        }
+        self.remove_bad_triangles(options, &orphans, &mut owners, &mut report);
+        if options.fix_group_indices {
+            self.fix_group_indices(&owners, &mut report);
+        }
+        if options.clamp_indices {
+            self.clamp_indices(&mut report);
+        }
+        if options.recompute_reference_counts {
+            self.recompute_reference_counts(&mut report);
+        }
+        report
+    }
+
+    fn remove_invalid_triangle_indices(&mut self, report: &mut RepairReport) {
+        let num_triangles = self.triangles.len();
+        for group in &mut self.groups {
+            let len = group.triangle_indices.len();
+            group
+                .triangle_indices
+                .retain(|&index| (index as usize) < num_triangles);
+            report.removed_triangle_indices += len - group.triangle_indices.len();
+        }
+    }
+
+    /// Remove groups which refer to missing triangles, returning a map from
+    /// old to new group indices. The triangles which belonged to a removed
+    /// group, by being listed in it or by their group index, are marked in
+    /// `orphans`.
+    fn remove_invalid_groups(
+        &mut self,
+        orphans: &mut [bool],
+        report: &mut RepairReport,
+    ) -> Vec<Option<usize>> {
+        let num_triangles = self.triangles.len();
+        let keep: Vec<bool> = self
+            .groups
+            .iter()
+            .map(|group| {
+                group
+                    .triangle_indices
+                    .iter()
+                    .all(|&index| (index as usize) < num_triangles)
+            })
+            .collect();
+        for (group, _) in self.groups.iter().zip(&keep).filter(|&(_, &keep)| !keep) {
+            for &index in &group.triangle_indices {
+                if let Some(orphan) = orphans.get_mut(index as usize) {
+                    *orphan = true;
+                }
+            }
+        }
+        for (orphan, triangle) in orphans.iter_mut().zip(&self.triangles) {
+            if keep.get(triangle.group_index as usize) == Some(&false) {
+                *orphan = true;
+            }
+        }
+
+        let map = index_map(&keep);
+        retain_marked(&mut self.groups, &keep);
+        report.removed_groups = map.iter().filter(|index| index.is_none()).count();
+
+        if let Some(ref mut comments) = self.comments {
+            remap_comments(&mut comments.group_comments, &map);
+        }
+        map
+    }
+
+    /// Find the group each triangle belongs to: the first group which lists
+    /// it, or else the group given by its group index before any groups were
+    /// removed.
+    fn triangle_owners(&self, group_map: &[Option<usize>]) -> Vec<Option<usize>> {
+        let mut owners = vec![None; self.triangles.len()];
+        for (group_index, group) in self.groups.iter().enumerate() {
+            for &index in &group.triangle_indices {
+                if let Some(owner) = owners.get_mut(index as usize) {
+                    if owner.is_none() {
+                        *owner = Some(group_index);
+                    }
+                }
+            }
+        }
+        for (owner, triangle) in owners.iter_mut().zip(&self.triangles) {
+            if owner.is_none() {
+                *owner = group_map
+                    .get(triangle.group_index as usize)
+                    .and_then(|&index| index);
+            }
+        }
+        owners
+    }
+
+    fn remove_bad_triangles(
+        &mut self,
+        options: &RepairOptions,
+        orphans: &[bool],
+        owners: &mut Vec<Option<usize>>,
+        report: &mut RepairReport,
+    ) {
+        let num_vertices = self.vertices.len();
+        let keep: Vec<bool> = self
+            .triangles
+            .iter()
+            .zip(owners.iter().zip(orphans))
+            .map(|(triangle, (owner, &orphan))| {
+                let missing_vertex = triangle
+                    .vertex_indices
+                    .iter()
+                    .any(|&index| index as usize >= num_vertices);
+                if options.remove_invalid_triangles && (missing_vertex || owner.is_none()) {
+                    if orphan && !missing_vertex {
+                        report.removed_orphan_triangles += 1;
+                    } else {
+                        report.removed_invalid_triangles += 1;
+                    }
+                    false
+                } else if options.remove_degenerate_triangles
+                    && is_degenerate(triangle, &self.vertices)
+                {
+                    report.removed_degenerate_triangles += 1;
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect();
+        if keep.iter().all(|&keep| keep) {
+            return;
+        }
+
+        let map = index_map(&keep);
+        retain_marked(&mut self.triangles, &keep);
+        retain_marked(owners, &keep);
+        for group in &mut self.groups {
+            group.triangle_indices = group
+                .triangle_indices
+                .iter()
+                .filter_map(|&index| map.get(index as usize).and_then(|&index| index))
+                .map(|index| index as u16)
+                .collect();
+        }
+    }
+
+    fn fix_group_indices(&mut self, owners: &[Option<usize>], report: &mut RepairReport) {
+        let mut listed = vec![false; self.triangles.len()];
+        for (group_index, group) in self.groups.iter_mut().enumerate() {
+            let len = group.triangle_indices.len();
+            group.triangle_indices.retain(|&index| {
+                let index = index as usize;
+                match owners.get(index) {
+                    Some(&Some(owner)) if owner == group_index && !listed[index] => {
+                        listed[index] = true;
+                        true
+                    }
+                    _ => false,
+                }
+            });
+            report.updated_group_memberships += len - group.triangle_indices.len();
+        }
+
+        for (index, (triangle, owner)) in self.triangles.iter_mut().zip(owners).enumerate() {
+            let owner = match *owner {
+                Some(owner) => owner,
+                None => continue,
+            };
+            if !listed[index] {
+                self.groups[owner].triangle_indices.push(index as u16);
+                report.updated_group_memberships += 1;
+            }
+            if triangle.group_index as usize != owner {
+                triangle.group_index = owner as u8;
+                report.updated_group_indices += 1;
+            }
+        }
+    }
+
+    fn clamp_indices(&mut self, report: &mut RepairReport) {
+        let num_materials = self.materials.len();
+        for group in &mut self.groups {
+            if !is_optional_index(group.material_index, num_materials) {
+                group.material_index = -1;
+                report.clamped_material_indices += 1;
+            }
+        }
+
+        let num_joints = self.joints.len();
+        for vertex in &mut self.vertices {
+            if !is_optional_index(vertex.bone_id, num_joints) {
+                vertex.bone_id = -1;
+                report.clamped_bone_ids += 1;
+            }
+        }
+        if let Some(ref mut info) = self.vertex_ex_info {
+            for index in 0..info.len() {
+                let (bone_ids, _) = info.bones_mut(index).unwrap();
+                for bone_id in bone_ids.iter_mut() {
+                    if !is_optional_index(*bone_id, num_joints) {
+                        *bone_id = -1;
+                        report.clamped_bone_ids += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    fn recompute_reference_counts(&mut self, report: &mut RepairReport) {
+        let counts = reference_counts(self.vertices.len(), &self.triangles);
+        for (vertex, count) in self.vertices.iter_mut().zip(counts) {
+            if vertex.reference_count != count {
+                vertex.reference_count = count;
+                report.updated_reference_counts += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::{fmt, str};
+
+#[cfg(feature = "encoding_rs")]
+use encoding_rs;
+
+/// A character encoding for the names, paths and comments stored in a model file.
+///
+/// MilkShape was a Windows application, so text is usually stored in the ANSI
+/// code page of the machine the file was created on. The default encoding is
+/// [`Windows1252`](struct.Windows1252.html); [`ShiftJis`](struct.ShiftJis.html)
+/// and [`Gbk`](struct.Gbk.html) are available with the `encoding_rs` feature.
+pub trait TextEncoding: fmt::Debug + Send + Sync {
+    /// Decode text, returning the decoded string and whether any malformed
+    /// sequences were replaced with U+FFFD.
+    fn decode(&self, bytes: &[u8]) -> (String, bool);
+
+    /// Return whether text decodes without any malformed sequences. The
+    /// default implementation decodes the text; encodings override it to check
+    /// without allocating.
+    fn is_valid(&self, bytes: &[u8]) -> bool {
+        !self.decode(bytes).1
+    }
+
+    /// Encode text, returning `None` if it contains characters which cannot be
+    /// represented in this encoding.
+    fn encode(&self, text: &str) -> Option<Vec<u8>>;
+}
+
+/// The UTF-8 encoding.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Utf8;
+
+impl TextEncoding for Utf8 {
+    fn decode(&self, bytes: &[u8]) -> (String, bool) {
+        match String::from_utf8(bytes.to_owned()) {
+            Ok(string) => (string, false),
+            Err(error) => (String::from_utf8_lossy(error.as_bytes()).into_owned(), true),
+        }
+    }
+
+    fn is_valid(&self, bytes: &[u8]) -> bool {
+        str::from_utf8(bytes).is_ok()
+    }
+
+    fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        Some(text.as_bytes().to_owned())
+    }
+}
+
+/// The Windows-1252 (Western European) encoding. Bytes which are not assigned a
+/// character are decoded as the corresponding C1 control character, so decoding
+/// never fails.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Windows1252;
+
+/// The characters for bytes 0x80 to 0x9F in Windows-1252. The rest of the code
+/// page is identical to ISO-8859-1.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+impl TextEncoding for Windows1252 {
+    fn decode(&self, bytes: &[u8]) -> (String, bool) {
+        let string = bytes
+            .iter()
+            .map(|&byte| match byte {
+                0x80..=0x9F => WINDOWS_1252_HIGH[byte as usize - 0x80],
+                _ => byte as char,
+            })
+            .collect();
+        (string, false)
+    }
+
+    fn is_valid(&self, _: &[u8]) -> bool {
+        true
+    }
+
+    fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        text.chars()
+            .map(|ch| match ch as u32 {
+                0x00..=0x7F | 0xA0..=0xFF => Some(ch as u8),
+                _ => WINDOWS_1252_HIGH
+                    .iter()
+                    .position(|&high| high == ch)
+                    .map(|i| 0x80 + i as u8),
+            })
+            .collect()
+    }
+}
+
+/// The Shift-JIS encoding, common in Japanese files.
+#[cfg(feature = "encoding_rs")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ShiftJis;
+
+#[cfg(feature = "encoding_rs")]
+impl TextEncoding for ShiftJis {
+    fn decode(&self, bytes: &[u8]) -> (String, bool) {
+        decode_with(encoding_rs::SHIFT_JIS, bytes)
+    }
+
+    fn is_valid(&self, bytes: &[u8]) -> bool {
+        is_valid_with(encoding_rs::SHIFT_JIS, bytes)
+    }
+
+    fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        encode_with(encoding_rs::SHIFT_JIS, text)
+    }
+}
+
+/// The GBK encoding, common in Simplified Chinese files.
+#[cfg(feature = "encoding_rs")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Gbk;
+
+#[cfg(feature = "encoding_rs")]
+impl TextEncoding for Gbk {
+    fn decode(&self, bytes: &[u8]) -> (String, bool) {
+        decode_with(encoding_rs::GBK, bytes)
+    }
+
+    fn is_valid(&self, bytes: &[u8]) -> bool {
+        is_valid_with(encoding_rs::GBK, bytes)
+    }
+
+    fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        encode_with(encoding_rs::GBK, text)
+    }
+}
+
+#[cfg(feature = "encoding_rs")]
+fn decode_with(encoding: &'static encoding_rs::Encoding, bytes: &[u8]) -> (String, bool) {
+    let (string, had_errors) = encoding.decode_without_bom_handling(bytes);
+    (string.into_owned(), had_errors)
+}
+
+/// Decode into a buffer on the stack, a piece at a time, stopping at the first
+/// malformed sequence.
+#[cfg(feature = "encoding_rs")]
+fn is_valid_with(encoding: &'static encoding_rs::Encoding, mut bytes: &[u8]) -> bool {
+    use encoding_rs::DecoderResult;
+
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut buf = [0; 256];
+    loop {
+        let (result, read, _) = decoder.decode_to_utf8_without_replacement(bytes, &mut buf, true);
+        match result {
+            DecoderResult::InputEmpty => return true,
+            DecoderResult::OutputFull => bytes = &bytes[read..],
+            DecoderResult::Malformed(..) => return false,
+        }
+    }
+}
+
+#[cfg(feature = "encoding_rs")]
+fn encode_with(encoding: &'static encoding_rs::Encoding, text: &str) -> Option<Vec<u8>> {
+    let (bytes, _, had_errors) = encoding.encode(text);
+    if had_errors {
+        None
+    } else {
+        Some(bytes.into_owned())
+    }
+}
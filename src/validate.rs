@@ -0,0 +1,240 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use super::{Comment, Model, Section};
+
+/// A broken reference between the elements of a model, found by
+/// [`Model::validate`](struct.Model.html#method.validate).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationIssue {
+    /// The section containing the element with the broken reference.
+    pub section: Section,
+    /// The index of the element within its section.
+    pub index: usize,
+    pub kind: IssueKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum IssueKind {
+    /// A triangle refers to a vertex which does not exist.
+    VertexIndex { vertex_index: u16 },
+    /// A group refers to a triangle which does not exist.
+    TriangleIndex { triangle_index: u16 },
+    /// A triangle refers to a group which does not exist.
+    GroupIndex { group_index: u8 },
+    /// A group refers to a material which does not exist.
+    MaterialIndex { material_index: i8 },
+    /// A vertex refers to a joint which does not exist.
+    BoneId { bone_id: i8 },
+    /// A joint's parent is not the name of any joint.
+    ParentName { parent_name: String },
+    /// A comment refers to an element of `target` which does not exist. The
+    /// index of the issue is the position of the comment in the list of
+    /// comments for `target`.
+    CommentIndex { target: Section, index: i32 },
+    /// An extra info section does not have one element for each element of
+    /// the section it extends.
+    LengthMismatch { expected: usize, found: usize },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "element {} of {} ", self.index, self.section)?;
+        match self.kind {
+            IssueKind::VertexIndex { vertex_index } => {
+                write!(f, "refers to missing vertex {}", vertex_index)
+            }
+            IssueKind::TriangleIndex { triangle_index } => {
+                write!(f, "refers to missing triangle {}", triangle_index)
+            }
+            IssueKind::GroupIndex { group_index } => {
+                write!(f, "refers to missing group {}", group_index)
+            }
+            IssueKind::MaterialIndex { material_index } => {
+                write!(f, "refers to missing material {}", material_index)
+            }
+            IssueKind::BoneId { bone_id } => write!(f, "refers to missing joint {}", bone_id),
+            IssueKind::ParentName { ref parent_name } => {
+                write!(f, "has missing parent joint {:?}", parent_name)
+            }
+            IssueKind::CommentIndex { target, index } => {
+                write!(f, "refers to missing element {} of {}", index, target)
+            }
+            IssueKind::LengthMismatch { expected, found } => {
+                write!(f, "has {} elements, expected {}", found, expected)
+            }
+        }
+    }
+}
+
+/// Returns true if `index` refers to an element of a section with `len`
+/// elements, or is -1 for no element.
+pub(crate) fn is_optional_index(index: i8, len: usize) -> bool {
+    index == -1 || (index >= 0 && (index as usize) < len)
+}
+
+struct Validator<'a> {
+    model: &'a Model,
+    issues: Vec<ValidationIssue>,
+}
+
+impl<'a> Validator<'a> {
+    fn issue(&mut self, section: Section, index: usize, kind: IssueKind) {
+        self.issues.push(ValidationIssue {
+            section,
+            index,
+            kind,
+        });
+    }
+
+    fn validate_vertices(&mut self) {
+        let model = self.model;
+        for (index, vertex) in model.vertices.iter().enumerate() {
+            if !is_optional_index(vertex.bone_id, model.joints.len()) {
+                let bone_id = vertex.bone_id;
+                self.issue(Section::Vertices, index, IssueKind::BoneId { bone_id });
+            }
+        }
+    }
+
+    fn validate_triangles(&mut self) {
+        let model = self.model;
+        for (index, triangle) in model.triangles.iter().enumerate() {
+            for &vertex_index in &triangle.vertex_indices {
+                if vertex_index as usize >= model.vertices.len() {
+                    let kind = IssueKind::VertexIndex { vertex_index };
+                    self.issue(Section::Triangles, index, kind);
+                }
+            }
+            let group_index = triangle.group_index;
+            if group_index as usize >= model.groups.len() {
+                let kind = IssueKind::GroupIndex { group_index };
+                self.issue(Section::Triangles, index, kind);
+            }
+        }
+    }
+
+    fn validate_groups(&mut self) {
+        let model = self.model;
+        for (index, group) in model.groups.iter().enumerate() {
+            for &triangle_index in &group.triangle_indices {
+                if triangle_index as usize >= model.triangles.len() {
+                    let kind = IssueKind::TriangleIndex { triangle_index };
+                    self.issue(Section::Groups, index, kind);
+                }
+            }
+            let material_index = group.material_index;
+            if !is_optional_index(material_index, model.materials.len()) {
+                let kind = IssueKind::MaterialIndex { material_index };
+                self.issue(Section::Groups, index, kind);
+            }
+        }
+    }
+
+    fn validate_joints(&mut self) {
+        let model = self.model;
+        let names: HashSet<&str> = model.joints.iter().map(|joint| &*joint.name).collect();
+        for (index, joint) in model.joints.iter().enumerate() {
+            if !joint.parent_name.is_empty() && !names.contains(&*joint.parent_name) {
+                let kind = IssueKind::ParentName {
+                    parent_name: joint.parent_name.clone(),
+                };
+                self.issue(Section::Joints, index, kind);
+            }
+        }
+    }
+
+    fn validate_comments(&mut self) {
+        let comments = match self.model.comments {
+            Some(ref comments) => comments,
+            None => return,
+        };
+        let model = self.model;
+        let lists: [(&[Comment], Section, usize); 3] = [
+            (
+                &comments.group_comments,
+                Section::Groups,
+                model.groups.len(),
+            ),
+            (
+                &comments.material_comments,
+                Section::Materials,
+                model.materials.len(),
+            ),
+            (
+                &comments.joint_comments,
+                Section::Joints,
+                model.joints.len(),
+            ),
+        ];
+        for &(list, target, len) in &lists {
+            for (position, comment) in list.iter().enumerate() {
+                if comment.index < 0 || comment.index as usize >= len {
+                    let kind = IssueKind::CommentIndex {
+                        target,
+                        index: comment.index,
+                    };
+                    self.issue(Section::Comments, position, kind);
+                }
+            }
+        }
+    }
+
+    fn validate_vertex_ex_info(&mut self) {
+        let model = self.model;
+        let info = match model.vertex_ex_info {
+            Some(ref info) => info,
+            None => return,
+        };
+        if info.len() != model.vertices.len() {
+            let kind = IssueKind::LengthMismatch {
+                expected: model.vertices.len(),
+                found: info.len(),
+            };
+            self.issue(Section::VertexExInfo, 0, kind);
+        }
+        for index in 0..info.len() {
+            let (bone_ids, _) = info.bones(index).unwrap();
+            for &bone_id in &bone_ids {
+                if !is_optional_index(bone_id, model.joints.len()) {
+                    self.issue(Section::VertexExInfo, index, IssueKind::BoneId { bone_id });
+                }
+            }
+        }
+    }
+
+    fn validate_joint_ex_info(&mut self) {
+        let model = self.model;
+        if let Some(ref info) = model.joint_ex_info {
+            if info.joint_ex.len() != model.joints.len() {
+                let kind = IssueKind::LengthMismatch {
+                    expected: model.joints.len(),
+                    found: info.joint_ex.len(),
+                };
+                self.issue(Section::JointExInfo, 0, kind);
+            }
+        }
+    }
+}
+
+impl Model {
+    /// Check that every index and name in the model refers to an element which
+    /// exists, returning an issue for each broken reference.
+    ///
+    /// A bone id or material index of -1 means no joint or material, and an
+    /// empty parent name means a joint has no parent.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut validator = Validator {
+            model: self,
+            issues: Vec::new(),
+        };
+        validator.validate_vertices();
+        validator.validate_triangles();
+        validator.validate_groups();
+        validator.validate_joints();
+        validator.validate_comments();
+        validator.validate_vertex_ex_info();
+        validator.validate_joint_ex_info();
+        validator.issues
+    }
+}
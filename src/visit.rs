@@ -0,0 +1,213 @@
+use std::io;
+
+use super::de::{self, Le};
+use super::model::*;
+use super::read::{BufReadExact, IoReader};
+use super::{ParseOptions, Reader, Result, Section, GROUP_MIN_SIZE};
+
+/// Callbacks for the elements of a model file, called in the order the
+/// elements are stored by [`visit`](fn.visit.html).
+///
+/// Every method does nothing by default, so a visitor only needs to implement
+/// the callbacks for the elements it is interested in.
+#[allow(unused_variables)]
+pub trait ModelVisitor {
+    fn visit_header(&mut self, header: &Header) {}
+
+    fn visit_vertex(&mut self, index: usize, vertex: &Vertex) {}
+
+    fn visit_triangle(&mut self, index: usize, triangle: &Triangle) {}
+
+    /// Called at the start of each group, before its triangle indices.
+    fn begin_group(&mut self, index: usize, flags: Flags, name: &str) {}
+
+    /// Called for each triangle index of the current group.
+    fn visit_group_triangle(&mut self, group: usize, triangle_index: u16) {}
+
+    /// Called at the end of each group with its material index, which is
+    /// stored after the triangle indices.
+    fn end_group(&mut self, index: usize, material_index: i8) {}
+
+    fn visit_material(&mut self, index: usize, material: &Material) {}
+
+    fn visit_key_frame_data(&mut self, key_frame_data: &KeyFrameData) {}
+
+    /// Called for each joint before its key frames. The key frames of `joint`
+    /// are always empty, as they are passed to `visit_key_frame_rot` and
+    /// `visit_key_frame_pos` instead.
+    fn visit_joint(&mut self, index: usize, joint: &Joint) {}
+
+    fn visit_key_frame_rot(&mut self, joint: usize, key_frame: &KeyFrameRot) {}
+
+    fn visit_key_frame_pos(&mut self, joint: usize, key_frame: &KeyFramePos) {}
+
+    /// Called at the start of the comments section, before any comments.
+    fn begin_comments(&mut self, sub_version: i32) {}
+
+    /// Called for each comment on a group, material or joint. `target` is the
+    /// section of the element the comment is attached to, and `index` is the
+    /// position of the comment in its list.
+    fn visit_comment(&mut self, target: Section, index: usize, comment: &Comment) {}
+
+    fn visit_model_comment(&mut self, comment: &Comment) {}
+
+    fn visit_vertex_ex_1(&mut self, index: usize, vertex_ex: &VertexEx1) {}
+
+    fn visit_vertex_ex_2(&mut self, index: usize, vertex_ex: &VertexEx2) {}
+
+    fn visit_vertex_ex_3(&mut self, index: usize, vertex_ex: &VertexEx3) {}
+
+    fn visit_joint_ex(&mut self, index: usize, joint_ex: &JointEx) {}
+
+    fn visit_model_ex_info(&mut self, model_ex_info: &ModelExInfo) {}
+}
+
+/// Read an ms3d model file from a reader, passing each element to a visitor
+/// instead of building a [`Model`](struct.Model.html).
+///
+/// The file is read one element at a time, so the memory used does not depend
+/// on the size of the model. Any data after the last section is ignored.
+pub fn visit<R: io::Read, V: ModelVisitor>(rdr: R, visitor: &mut V) -> Result<()> {
+    Reader::new(IoReader::new(rdr), ParseOptions::default()).visit_model(visitor)
+}
+
+impl<R: BufReadExact> Reader<R> {
+    fn visit_model<V: ModelVisitor>(&mut self, visitor: &mut V) -> Result<()> {
+        let header = self.read_header()?;
+        visitor.visit_header(&header);
+
+        self.begin_section(Section::Vertices);
+        let num_vertices = self.read_u16()? as usize;
+        self.for_each_element(num_vertices, de::Vertex::SIZE, |this| {
+            let vertex = this.read_vertex()?;
+            visitor.visit_vertex(this.index, &vertex);
+            Ok(())
+        })?;
+
+        self.begin_section(Section::Triangles);
+        let len = self.read_u16()? as usize;
+        self.for_each_element(len, de::Triangle::SIZE, |this| {
+            let triangle = this.read_triangle()?;
+            visitor.visit_triangle(this.index, &triangle);
+            Ok(())
+        })?;
+
+        self.begin_section(Section::Groups);
+        let len = self.read_u16()? as usize;
+        self.for_each_element(len, GROUP_MIN_SIZE, |this| {
+            let index = this.index;
+            let (group, num_triangles) = this.read_group_prefix()?;
+            visitor.begin_group(index, group.flags, &group.name);
+            this.check_len(num_triangles as usize, 2)?;
+            for _ in 0..num_triangles {
+                visitor.visit_group_triangle(index, this.read_u16()?);
+            }
+            visitor.end_group(index, this.read_group_suffix()?);
+            Ok(())
+        })?;
+
+        self.begin_section(Section::Materials);
+        let len = self.read_u16()? as usize;
+        self.for_each_element(len, de::Material::SIZE, |this| {
+            let material = this.read_material()?;
+            visitor.visit_material(this.index, &material);
+            Ok(())
+        })?;
+
+        let key_frame_data = self.read_key_frame_data()?;
+        visitor.visit_key_frame_data(&key_frame_data);
+
+        self.begin_section(Section::Joints);
+        let num_joints = self.read_u16()? as usize;
+        self.for_each_element(num_joints, de::JointPrefix::SIZE, |this| {
+            let index = this.index;
+            let (joint, num_key_frames_rot, num_key_frames_trans) = this.read_joint_prefix()?;
+            visitor.visit_joint(index, &joint);
+            this.check_len(num_key_frames_rot as usize, de::KeyFrameRot::SIZE)?;
+            for _ in 0..num_key_frames_rot {
+                visitor.visit_key_frame_rot(index, &this.read_key_frame_rot()?);
+            }
+            this.check_len(num_key_frames_trans as usize, de::KeyFramePos::SIZE)?;
+            for _ in 0..num_key_frames_trans {
+                visitor.visit_key_frame_pos(index, &this.read_key_frame_pos()?);
+            }
+            Ok(())
+        })?;
+
+        self.read_optional(|this| this.visit_comments(visitor))?;
+        self.read_optional(|this| this.visit_vertex_ex_info(num_vertices, visitor))?;
+        self.read_optional(|this| this.visit_joint_ex_info(num_joints, visitor))?;
+        if let Some(model_ex_info) = self.read_optional(Self::read_model_ex_info)? {
+            visitor.visit_model_ex_info(&model_ex_info);
+        }
+        Ok(())
+    }
+
+    fn visit_comments<V: ModelVisitor>(&mut self, visitor: &mut V) -> Result<Option<()>> {
+        self.begin_section(Section::Comments);
+        let sub_version = match self.read_sub_version(&[1])? {
+            Some(sub_version) => sub_version,
+            None => return Ok(None),
+        };
+        visitor.begin_comments(sub_version);
+        let mut total = 0;
+        for &target in &[Section::Groups, Section::Materials, Section::Joints] {
+            let len = self.read_comment_count(&mut total)?;
+            self.for_each_element(len, de::CommentPrefix::SIZE, |this| {
+                let comment = this.read_comment()?;
+                visitor.visit_comment(target, this.index, &comment);
+                Ok(())
+            })?;
+        }
+        let len = self.read_model_comment_count(&mut total)?;
+        self.for_each_element(len, de::CommentPrefix::SIZE, |this| {
+            visitor.visit_model_comment(&this.read_comment()?);
+            Ok(())
+        })?;
+        Ok(Some(()))
+    }
+
+    fn visit_vertex_ex_info<V: ModelVisitor>(
+        &mut self,
+        len: usize,
+        visitor: &mut V,
+    ) -> Result<Option<()>> {
+        self.begin_section(Section::VertexExInfo);
+        match self.read_sub_version(&[1, 2, 3])? {
+            Some(1) => self.for_each_element(len, de::VertexEx1::SIZE, |this| {
+                let vertex_ex = this.read_vertex_ex_1()?;
+                visitor.visit_vertex_ex_1(this.index, &vertex_ex);
+                Ok(())
+            })?,
+            Some(2) => self.for_each_element(len, de::VertexEx2::SIZE, |this| {
+                let vertex_ex = this.read_vertex_ex_2()?;
+                visitor.visit_vertex_ex_2(this.index, &vertex_ex);
+                Ok(())
+            })?,
+            Some(_) => self.for_each_element(len, de::VertexEx3::SIZE, |this| {
+                let vertex_ex = this.read_vertex_ex_3()?;
+                visitor.visit_vertex_ex_3(this.index, &vertex_ex);
+                Ok(())
+            })?,
+            None => return Ok(None),
+        }
+        Ok(Some(()))
+    }
+
+    fn visit_joint_ex_info<V: ModelVisitor>(
+        &mut self,
+        len: usize,
+        visitor: &mut V,
+    ) -> Result<Option<()>> {
+        self.begin_section(Section::JointExInfo);
+        if self.read_sub_version(&[1])?.is_none() {
+            return Ok(None);
+        }
+        self.for_each_element(len, de::JointEx::SIZE, |this| {
+            let joint_ex = this.read_joint_ex()?;
+            visitor.visit_joint_ex(this.index, &joint_ex);
+            Ok(())
+        })?;
+        Ok(Some(()))
+    }
+}
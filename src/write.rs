@@ -0,0 +1,468 @@
+use std::borrow::Cow;
+use std::io;
+use std::mem;
+use std::path::Path;
+
+use super::de::{self, Le};
+use super::model::*;
+use super::{trim_nul, Error, Result, Section, WriteOptions};
+
+pub(crate) struct Writer<W: io::Write> {
+    wtr: W,
+    options: WriteOptions,
+    section: Section,
+    index: usize,
+    pos: u64,
+    buf: Vec<u8>,
+}
+
+impl<W: io::Write> Writer<W> {
+    pub fn new(wtr: W, options: WriteOptions) -> Self {
+        Writer {
+            wtr,
+            options,
+            section: Section::Header,
+            index: 0,
+            pos: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn write_model(&mut self, model: &Model) -> Result<()> {
+        // Check the optional sections up front, so an error does not leave a
+        // truncated file behind.
+        self.check_optional_sections(model)?;
+
+        self.write_header(&model.header)?;
+        self.write_vertices(&model.vertices)?;
+        self.write_triangles(&model.triangles)?;
+        self.write_groups(&model.groups)?;
+        self.write_materials(&model.materials)?;
+        self.write_key_frame_data(&model.key_frame_data)?;
+        self.write_joints(&model.joints)?;
+        if let Some(ref comments) = model.comments {
+            self.write_comments(comments)?;
+        }
+        if let Some(ref vertex_ex_info) = model.vertex_ex_info {
+            self.write_vertex_ex_info(vertex_ex_info)?;
+        }
+        if let Some(ref joint_ex_info) = model.joint_ex_info {
+            self.write_joint_ex_info(joint_ex_info)?;
+        }
+        if let Some(ref model_ex_info) = model.model_ex_info {
+            self.write_model_ex_info(model_ex_info)?;
+        }
+        self.write_bytes(&model.unknown_trailer)
+    }
+
+    fn check_optional_sections(&mut self, model: &Model) -> Result<()> {
+        // The optional sections can only be omitted from the end of the file.
+        let present = [
+            model.comments.is_some(),
+            model.vertex_ex_info.is_some(),
+            model.joint_ex_info.is_some(),
+            model.model_ex_info.is_some(),
+        ];
+        let len = present.iter().rposition(|&p| p).map_or(0, |i| i + 1);
+        if let Some(i) = present[..len].iter().position(|&p| !p) {
+            let section = [
+                Section::Comments,
+                Section::VertexExInfo,
+                Section::JointExInfo,
+                Section::ModelExInfo,
+            ][i];
+            return Err(Error::MissingSection { section });
+        }
+        if len != 0 && model.header.version < 4 {
+            return Err(Error::UnsupportedSection {
+                section: Section::Comments,
+                version: model.header.version,
+            });
+        }
+
+        if let Some(ref vertex_ex_info) = model.vertex_ex_info {
+            self.begin_section(Section::VertexExInfo);
+            self.ensure_len(vertex_ex_info.len(), model.vertices.len())?;
+        }
+        if let Some(ref joint_ex_info) = model.joint_ex_info {
+            self.begin_section(Section::JointExInfo);
+            self.ensure_len(joint_ex_info.joint_ex.len(), model.joints.len())?;
+        }
+        Ok(())
+    }
+
+    fn write_header(&mut self, header: &Header) -> Result<()> {
+        self.begin_section(Section::Header);
+        if header.version != 3 && header.version != 4 {
+            return Err(Error::UnsupportedVersion {
+                version: header.version,
+                offset: self.pos + 10,
+            });
+        }
+        let mut id = [0; 10];
+        id.copy_from_slice(b"MS3D000000");
+        self.write_type(&de::Header {
+            id,
+            version: header.version,
+        })
+    }
+
+    fn write_vertices(&mut self, vertices: &[Vertex]) -> Result<()> {
+        self.begin_section(Section::Vertices);
+        self.write_len(vertices.len())?;
+        self.write_elements(vertices, Self::write_vertex)
+    }
+
+    fn write_vertex(&mut self, vertex: &Vertex) -> Result<()> {
+        self.write_type(&de::Vertex {
+            flags: vertex.flags.bits() as u8,
+            vertex: vertex.vertex,
+            bone_id: vertex.bone_id,
+            reference_count: vertex.reference_count,
+        })
+    }
+
+    fn write_triangles(&mut self, triangles: &[Triangle]) -> Result<()> {
+        self.begin_section(Section::Triangles);
+        self.write_len(triangles.len())?;
+        self.write_elements(triangles, Self::write_triangle)
+    }
+
+    fn write_triangle(&mut self, triangle: &Triangle) -> Result<()> {
+        self.write_type(&de::Triangle {
+            flags: triangle.flags.bits(),
+            vertex_indices: triangle.vertex_indices,
+            vertex_normals: triangle.vertex_normals,
+            s: triangle.s,
+            t: triangle.t,
+            smoothing_group: triangle.smoothing_group,
+            group_index: triangle.group_index,
+        })
+    }
+
+    fn write_groups(&mut self, groups: &[Group]) -> Result<()> {
+        self.begin_section(Section::Groups);
+        self.write_len(groups.len())?;
+        self.write_elements(groups, Self::write_group)
+    }
+
+    fn write_group(&mut self, group: &Group) -> Result<()> {
+        let num_triangles = self.convert_len(group.triangle_indices.len())?;
+        self.write_type(&de::GroupPrefix {
+            flags: group.flags.bits() as u8,
+            name: self.convert_string(&group.name, &group.raw_name)?,
+            num_triangles,
+        })?;
+        self.write_slice(&group.triangle_indices, |this, &index| {
+            this.write_u16(index)
+        })?;
+        self.write_type(&de::GroupSuffix {
+            material_index: group.material_index,
+        })
+    }
+
+    fn write_materials(&mut self, materials: &[Material]) -> Result<()> {
+        self.begin_section(Section::Materials);
+        self.write_len(materials.len())?;
+        self.write_elements(materials, Self::write_material)
+    }
+
+    pub fn write_material(&mut self, material: &Material) -> Result<()> {
+        self.write_type(&de::Material {
+            name: self.convert_string(&material.name, &material.raw_name)?,
+            ambient: material.ambient,
+            diffuse: material.diffuse,
+            specular: material.specular,
+            emissive: material.emissive,
+            shininess: material.shininess,
+            transparency: material.transparency,
+            mode: material.mode,
+            texture: self.convert_path(&material.texture, &material.raw_texture)?,
+            alphamap: self.convert_path(&material.alphamap, &material.raw_alphamap)?,
+        })
+    }
+
+    fn write_key_frame_data(&mut self, key_frame_data: &KeyFrameData) -> Result<()> {
+        self.begin_section(Section::KeyFrameData);
+        self.write_type(&de::KeyFrameData {
+            animation_fps: key_frame_data.animation_fps,
+            current_time: key_frame_data.current_time,
+            total_frames: key_frame_data.total_frames,
+        })
+    }
+
+    fn write_joints(&mut self, joints: &[Joint]) -> Result<()> {
+        self.begin_section(Section::Joints);
+        self.write_len(joints.len())?;
+        self.write_elements(joints, Self::write_joint)
+    }
+
+    fn write_joint(&mut self, joint: &Joint) -> Result<()> {
+        self.write_type(&de::JointPrefix {
+            flags: joint.flags.bits() as u8,
+            name: self.convert_string(&joint.name, &joint.raw_name)?,
+            parent_name: self.convert_string(&joint.parent_name, &joint.raw_parent_name)?,
+            rotation: joint.rotation,
+            position: joint.position,
+            num_key_frames_rot: self.convert_len(joint.key_frames_rot.len())?,
+            num_key_frames_trans: self.convert_len(joint.key_frames_trans.len())?,
+        })?;
+        self.write_slice(&joint.key_frames_rot, Self::write_key_frame_rot)?;
+        self.write_slice(&joint.key_frames_trans, Self::write_key_frame_pos)
+    }
+
+    fn write_key_frame_rot(&mut self, key_frame: &KeyFrameRot) -> Result<()> {
+        self.write_type(&de::KeyFrameRot {
+            time: key_frame.time,
+            rotation: key_frame.rotation,
+        })
+    }
+
+    fn write_key_frame_pos(&mut self, key_frame: &KeyFramePos) -> Result<()> {
+        self.write_type(&de::KeyFramePos {
+            time: key_frame.time,
+            position: key_frame.position,
+        })
+    }
+
+    fn write_comments(&mut self, comments: &Comments) -> Result<()> {
+        self.begin_section(Section::Comments);
+        self.write_sub_version(comments.sub_version, &[1])?;
+        self.write_i32(comments.group_comments.len() as i32)?;
+        self.write_elements(&comments.group_comments, Self::write_comment)?;
+        self.write_i32(comments.material_comments.len() as i32)?;
+        self.write_elements(&comments.material_comments, Self::write_comment)?;
+        self.write_i32(comments.joint_comments.len() as i32)?;
+        self.write_elements(&comments.joint_comments, Self::write_comment)?;
+        match comments.model_comment {
+            Some(ref comment) => {
+                self.write_i32(1)?;
+                self.index = 0;
+                self.write_comment(comment)
+            }
+            None => self.write_i32(0),
+        }
+    }
+
+    fn write_comment(&mut self, comment: &Comment) -> Result<()> {
+        let bytes = if self.is_unchanged(&comment.comment, &comment.raw_comment) {
+            Cow::Borrowed(&comment.raw_comment[..])
+        } else {
+            Cow::Owned(self.encode(&comment.comment)?)
+        };
+        self.write_type(&de::CommentPrefix {
+            index: comment.index,
+            comment_length: bytes.len() as i32,
+        })?;
+        self.write_bytes(&bytes)
+    }
+
+    fn write_vertex_ex_info(&mut self, vertex_ex_info: &VertexExInfo) -> Result<()> {
+        use VertexExInfo::*;
+
+        self.begin_section(Section::VertexExInfo);
+        match *vertex_ex_info {
+            SubVersion1(ref vertex_ex) => {
+                self.write_i32(1)?;
+                self.write_elements(vertex_ex, Self::write_vertex_ex_1)
+            }
+            SubVersion2(ref vertex_ex) => {
+                self.write_i32(2)?;
+                self.write_elements(vertex_ex, Self::write_vertex_ex_2)
+            }
+            SubVersion3(ref vertex_ex) => {
+                self.write_i32(3)?;
+                self.write_elements(vertex_ex, Self::write_vertex_ex_3)
+            }
+        }
+    }
+
+    fn write_vertex_ex_1(&mut self, vertex_ex: &VertexEx1) -> Result<()> {
+        self.write_type(&de::VertexEx1 {
+            bone_ids: vertex_ex.bone_ids,
+            weights: vertex_ex.weights,
+        })
+    }
+
+    fn write_vertex_ex_2(&mut self, vertex_ex: &VertexEx2) -> Result<()> {
+        self.write_type(&de::VertexEx2 {
+            bone_ids: vertex_ex.bone_ids,
+            weights: vertex_ex.weights,
+            extra: vertex_ex.extra,
+        })
+    }
+
+    fn write_vertex_ex_3(&mut self, vertex_ex: &VertexEx3) -> Result<()> {
+        self.write_type(&de::VertexEx3 {
+            bone_ids: vertex_ex.bone_ids,
+            weights: vertex_ex.weights,
+            extra: vertex_ex.extra,
+        })
+    }
+
+    fn write_joint_ex_info(&mut self, joint_ex_info: &JointExInfo) -> Result<()> {
+        self.begin_section(Section::JointExInfo);
+        self.write_sub_version(joint_ex_info.sub_version, &[1])?;
+        self.write_elements(&joint_ex_info.joint_ex, Self::write_joint_ex)
+    }
+
+    pub fn write_joint_ex(&mut self, joint_ex: &JointEx) -> Result<()> {
+        self.write_type(&de::JointEx {
+            color: joint_ex.color,
+        })
+    }
+
+    fn write_model_ex_info(&mut self, model_ex_info: &ModelExInfo) -> Result<()> {
+        self.begin_section(Section::ModelExInfo);
+        self.write_sub_version(model_ex_info.sub_version, &[1])?;
+        self.write_model_ex(&model_ex_info.model_ex)
+    }
+
+    fn write_model_ex(&mut self, model_ex: &ModelEx) -> Result<()> {
+        self.write_type(&de::ModelEx {
+            joint_size: model_ex.joint_size,
+            transparency_mode: model_ex.transparency_mode,
+            alpha_ref: model_ex.alpha_ref,
+        })
+    }
+
+    fn begin_section(&mut self, section: Section) {
+        self.section = section;
+        self.index = 0;
+    }
+
+    /// Start writing element `index` of `section`, which is at byte offset
+    /// `pos` of the file, for patching a single element of an existing file.
+    pub fn begin_element(&mut self, section: Section, index: usize, pos: u64) {
+        self.section = section;
+        self.index = index;
+        self.pos = pos;
+    }
+
+    fn write_sub_version(&mut self, sub_version: i32, supported: &[i32]) -> Result<()> {
+        if !supported.contains(&sub_version) {
+            return Err(Error::UnsupportedSubVersion {
+                section: self.section,
+                sub_version,
+                offset: self.pos,
+            });
+        }
+        self.write_i32(sub_version)
+    }
+
+    /// Write the elements of the current section, keeping track of the index
+    /// of the current element for error reporting.
+    fn write_elements<T, F>(&mut self, slice: &[T], f: F) -> Result<()>
+    where
+        F: Fn(&mut Self, &T) -> Result<()>,
+    {
+        slice.iter().enumerate().try_for_each(|(index, item)| {
+            self.index = index;
+            f(self, item)
+        })
+    }
+
+    fn write_slice<T, F>(&mut self, slice: &[T], f: F) -> Result<()>
+    where
+        F: Fn(&mut Self, &T) -> Result<()>,
+    {
+        slice.iter().try_for_each(|item| f(self, item))
+    }
+
+    fn write_len(&mut self, len: usize) -> Result<()> {
+        let len = self.convert_len(len)?;
+        self.write_u16(len)
+    }
+
+    fn write_u16(&mut self, value: u16) -> Result<()> {
+        self.write_type(&value)
+    }
+
+    fn write_i32(&mut self, value: i32) -> Result<()> {
+        self.write_type(&value)
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        let offset = self.pos;
+        self.wtr
+            .write_all(bytes)
+            .map_err(|error| Error::Io { offset, error })?;
+        self.pos += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn write_type<T: Le>(&mut self, value: &T) -> Result<()> {
+        let mut buf = mem::take(&mut self.buf);
+        buf.clear();
+        value.write_le(&mut buf);
+        let result = self.write_bytes(&buf);
+        self.buf = buf;
+        result
+    }
+
+    /// Convert a string to a fixed size field. If the string is unchanged
+    /// since it was read, the raw bytes are written back as they were.
+    pub fn convert_string<const N: usize>(&self, string: &str, raw: &[u8]) -> Result<[u8; N]> {
+        let bytes = if self.is_unchanged(string, trim_nul(raw)) {
+            Cow::Borrowed(if raw.len() <= N { raw } else { trim_nul(raw) })
+        } else {
+            Cow::Owned(self.encode(string)?)
+        };
+        if bytes.len() > N {
+            return Err(Error::StringTooLong {
+                section: self.section,
+                index: self.index,
+                len: bytes.len(),
+                max: N,
+            });
+        }
+        let mut array = [0; N];
+        array[..bytes.len()].copy_from_slice(&bytes);
+        Ok(array)
+    }
+
+    fn convert_path<const N: usize>(&self, path: &Path, raw: &[u8]) -> Result<[u8; N]> {
+        match path.to_str() {
+            Some(string) => self.convert_string(string, raw),
+            None => Err(Error::InvalidPath {
+                section: self.section,
+                index: self.index,
+            }),
+        }
+    }
+
+    fn is_unchanged(&self, string: &str, raw: &[u8]) -> bool {
+        self.options.encoding.decode(raw).0 == string
+    }
+
+    fn encode(&self, string: &str) -> Result<Vec<u8>> {
+        self.options
+            .encoding
+            .encode(string)
+            .ok_or(Error::UnencodableText {
+                section: self.section,
+                index: self.index,
+            })
+    }
+
+    fn convert_len(&self, len: usize) -> Result<u16> {
+        if len > u16::MAX as usize {
+            return Err(Error::TooManyElements {
+                section: self.section,
+                len,
+            });
+        }
+        Ok(len as u16)
+    }
+
+    fn ensure_len(&self, len: usize, expected: usize) -> Result<()> {
+        if len != expected {
+            return Err(Error::LengthMismatch {
+                section: self.section,
+                expected,
+                found: len,
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "tokio")]
+
+extern crate ms3d;
+extern crate tokio;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use ms3d::{Error, Limit, Model, ParseOptions, Section};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+/// A future which counts how many times it is polled.
+struct CountPolls<F> {
+    future: Pin<Box<F>>,
+    polls: usize,
+}
+
+impl<F: Future> Future for CountPolls<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<F::Output> {
+        self.polls += 1;
+        self.future.as_mut().poll(cx)
+    }
+}
+
+#[test]
+fn test_async_reader() {
+    let mut future = CountPolls {
+        future: Box::pin(Model::from_async_reader(BYTES)),
+        polls: 0,
+    };
+    let model = block_on(&mut future).unwrap();
+    assert_eq!(model, Model::from_bytes(BYTES).unwrap());
+    // An in-memory reader is always ready, so the future must yield between
+    // chunks on its own.
+    assert!(future.polls > BYTES.len() / 8192);
+}
+
+#[test]
+fn test_async_reader_truncated() {
+    match block_on(Model::from_async_reader(&BYTES[..100])).unwrap_err() {
+        Error::UnexpectedEof {
+            section: Section::Vertices,
+            ..
+        } => {}
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    let mut options = ParseOptions::default();
+    options.limits.max_total_bytes = 100;
+    match block_on(Model::from_async_reader_with(BYTES, options)).unwrap_err() {
+        Error::LimitExceeded {
+            section: Section::Vertices,
+            limit: Limit::TotalBytes,
+            offset: 16,
+        } => {}
+        err => panic!("unexpected error {:?}", err),
+    }
+}
@@ -0,0 +1,111 @@
+extern crate ms3d;
+
+use std::path::PathBuf;
+
+use ms3d::{Error, Model, ModelBuilder, ModelEx, ModelExInfo, Section, VertexEx1, VertexExInfo};
+
+#[test]
+fn test_builder() {
+    let mut builder = ModelBuilder::new();
+    let root = builder.add_joint("root", None).unwrap();
+    let arm = builder.add_joint("arm", Some(root)).unwrap();
+    let material = builder
+        .add_material("skin", PathBuf::from("skin.tga"))
+        .unwrap();
+    let group = builder.add_group("quad", Some(material)).unwrap();
+    let a = builder.add_vertex([0.0, 0.0, 0.0], Some(root)).unwrap();
+    let b = builder.add_vertex([1.0, 0.0, 0.0], Some(arm)).unwrap();
+    let c = builder.add_vertex([1.0, 1.0, 0.0], Some(arm)).unwrap();
+    let d = builder.add_vertex([0.0, 1.0, 0.0], None).unwrap();
+    let normals = [[0.0, 0.0, 1.0]; 3];
+    builder
+        .add_triangle(
+            group,
+            [a, b, c],
+            normals,
+            [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]],
+        )
+        .unwrap();
+    builder
+        .add_triangle(
+            group,
+            [a, c, d],
+            normals,
+            [[0.0, 0.0], [1.0, 1.0], [0.0, 1.0]],
+        )
+        .unwrap();
+    let model = builder.build();
+
+    assert_eq!(model.validate(), vec![]);
+    let counts: Vec<_> = model.vertices.iter().map(|v| v.reference_count).collect();
+    assert_eq!(counts, [2, 1, 2, 1]);
+    assert_eq!(model.groups[0].triangle_indices, [0, 1]);
+    assert_eq!(model.groups[0].material_index, 0);
+    assert_eq!(model.triangles[1].s, [0.0, 1.0, 0.0]);
+    assert_eq!(model.triangles[1].t, [0.0, 1.0, 1.0]);
+    assert_eq!(model.joints[1].parent_name, "root");
+    assert_eq!(model.vertex_ex_info.as_ref().unwrap().len(), 4);
+    assert_eq!(model.joint_ex_info.as_ref().unwrap().joint_ex.len(), 2);
+
+    let read = Model::from_bytes(&model.to_bytes().unwrap()).unwrap();
+    assert_eq!(read.groups[0].name, "quad");
+    assert_eq!(read.materials[0].texture, PathBuf::from("skin.tga"));
+    assert_eq!(read.triangles, model.triangles);
+}
+
+#[test]
+fn test_builder_errors() {
+    let mut builder = ModelBuilder::new();
+    let vertex = builder.add_vertex([0.0; 3], None).unwrap();
+    match builder.add_triangle(0, [vertex; 3], [[0.0; 3]; 3], [[0.0; 2]; 3]) {
+        Err(Error::IndexOutOfRange {
+            section: Section::Groups,
+            index: 0,
+            len: 0,
+        }) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(builder.model_mut().triangles.is_empty());
+
+    for i in 0..128 {
+        builder.add_joint(&format!("joint{}", i), None).unwrap();
+    }
+    match builder.add_joint("joint128", None) {
+        Err(Error::TooManyElements {
+            section: Section::Joints,
+            len: 129,
+        }) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_builder_keeps_sections() {
+    let mut builder = ModelBuilder::new();
+    builder.model_mut().model_ex_info = Some(ModelExInfo {
+        sub_version: 1,
+        model_ex: ModelEx {
+            joint_size: 7.0,
+            transparency_mode: 1,
+            alpha_ref: 0.25,
+        },
+    });
+    let extra = VertexEx1 {
+        bone_ids: [0, -1, -1],
+        weights: [50, 0, 0],
+    };
+    builder.model_mut().vertex_ex_info = Some(VertexExInfo::SubVersion1(vec![extra.clone()]));
+    builder.add_vertex([0.0; 3], None).unwrap();
+    builder.add_vertex([1.0; 3], None).unwrap();
+    let model = builder.build();
+
+    assert_eq!(model.model_ex_info.unwrap().model_ex.joint_size, 7.0);
+    match model.vertex_ex_info {
+        Some(VertexExInfo::SubVersion1(ref vertex_ex)) => {
+            assert_eq!(vertex_ex.len(), 2);
+            assert_eq!(vertex_ex[0], extra);
+            assert_eq!(vertex_ex[1].bone_ids, [-1; 3]);
+        }
+        ref info => panic!("unexpected vertex extra info {:?}", info),
+    }
+}
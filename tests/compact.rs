@@ -0,0 +1,52 @@
+extern crate ms3d;
+
+use std::path::PathBuf;
+
+use ms3d::{CompactReport, Model, ModelBuilder};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+#[test]
+fn test_compact_unchanged() {
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    let original = model.clone();
+    assert!(model.compact().is_empty());
+    assert_eq!(model, original);
+}
+
+#[test]
+fn test_compact() {
+    let mut builder = ModelBuilder::new();
+    let root = builder.add_joint("root", None).unwrap();
+    builder.add_joint("orphan", None).unwrap();
+    let arm = builder.add_joint("arm", Some(root)).unwrap();
+    builder.add_joint("finger", Some(arm)).unwrap();
+    builder.add_material("unused", PathBuf::new()).unwrap();
+    let material = builder.add_material("used", PathBuf::new()).unwrap();
+    let group = builder.add_group("group", Some(material)).unwrap();
+    builder.add_vertex([9.0; 3], Some(root)).unwrap();
+    let a = builder.add_vertex([0.0, 0.0, 0.0], Some(arm)).unwrap();
+    let b = builder.add_vertex([1.0, 0.0, 0.0], None).unwrap();
+    let c = builder.add_vertex([0.0, 1.0, 0.0], None).unwrap();
+    builder
+        .add_triangle(group, [a, b, c], [[0.0; 3]; 3], [[0.0; 2]; 3])
+        .unwrap();
+    let mut model = builder.build();
+
+    assert_eq!(
+        model.compact(),
+        CompactReport {
+            removed_vertices: 1,
+            removed_materials: 1,
+            removed_joints: 2,
+        }
+    );
+    let names: Vec<_> = model.joints.iter().map(|joint| &joint.name[..]).collect();
+    assert_eq!(names, ["root", "arm"]);
+    assert_eq!(model.vertices[0].bone_id, 1);
+    assert_eq!(model.triangles[0].vertex_indices, [0, 1, 2]);
+    assert_eq!(model.groups[0].material_index, 0);
+    assert_eq!(model.vertex_ex_info.as_ref().unwrap().len(), 3);
+    assert_eq!(model.joint_ex_info.as_ref().unwrap().joint_ex.len(), 2);
+    assert_eq!(model.validate(), vec![]);
+}
@@ -1,7 +1,9 @@
 extern crate ms3d;
 
 use std::fs::File;
-use ms3d::Model;
+use std::io::{self, Read};
+use std::path::Path;
+use ms3d::{Error, Flags, Limit, Limits, Model, ParseOptions, Section, Utf8, Warning, WarningKind};
 
 const BYTES: &[u8] = include_bytes!("POA.ms3d");
 
@@ -13,4 +15,434 @@ fn test_reader() {
 #[test]
 fn test_slice() {
     Model::from_bytes(BYTES).unwrap();
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_open() {
+    let model = Model::open("tests/POA.ms3d").unwrap();
+    assert_eq!(model, Model::from_bytes(BYTES).unwrap());
+
+    let err = Model::open("tests/missing.ms3d").unwrap_err();
+    assert_eq!(err.to_string(), "error reading tests/missing.ms3d");
+    assert!(std::error::Error::source(&err).is_some());
+    match err {
+        Error::File { path, error } => {
+            assert_eq!(path, Path::new("tests/missing.ms3d"));
+            match *error {
+                Error::Io { offset: 0, .. } => {}
+                err => panic!("unexpected error {:?}", err),
+            }
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+/// A reader which returns at most one byte from each read.
+struct ByteReader<'a>(&'a [u8]);
+
+impl<'a> Read for ByteReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.0.len()).min(1);
+        buf[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn test_short_reads() {
+    let model = Model::from_reader(ByteReader(BYTES)).unwrap();
+    assert_eq!(model, Model::from_bytes(BYTES).unwrap());
+}
+
+/// Offset of the comments section in POA.ms3d, where files written by older
+/// versions of MilkShape end.
+fn joints_end() -> usize {
+    let model = Model::from_bytes(BYTES).unwrap();
+    let mut trailing = model.clone();
+    trailing.comments = None;
+    trailing.vertex_ex_info = None;
+    trailing.joint_ex_info = None;
+    trailing.model_ex_info = None;
+    trailing.to_bytes().unwrap().len()
+}
+
+#[test]
+fn test_missing_optional_sections() {
+    let len = joints_end();
+    let model = Model::from_bytes(&BYTES[..len]).unwrap();
+    assert!(model.comments.is_none());
+    assert!(model.vertex_ex_info.is_none());
+    assert!(model.joint_ex_info.is_none());
+    assert!(model.model_ex_info.is_none());
+
+    let model = Model::from_reader(&BYTES[..len]).unwrap();
+    assert!(model.comments.is_none());
+
+    // A file which ends between the comments and the vertex extra info.
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    model.vertex_ex_info = None;
+    model.joint_ex_info = None;
+    model.model_ex_info = None;
+    let bytes = model.to_bytes().unwrap();
+    assert_eq!(bytes[..], BYTES[..bytes.len()]);
+    for parsed in &[
+        Model::from_bytes(&bytes).unwrap(),
+        Model::from_reader(&bytes[..]).unwrap(),
+    ] {
+        assert_eq!(parsed.comments, model.comments);
+        assert!(parsed.vertex_ex_info.is_none());
+        assert!(parsed.joint_ex_info.is_none());
+        assert!(parsed.model_ex_info.is_none());
+    }
+}
+
+#[test]
+fn test_truncated_optional_section() {
+    let len = joints_end();
+    assert!(Model::from_bytes(&BYTES[..len + 2]).is_err());
+    assert!(Model::from_reader(&BYTES[..len + 2]).is_err());
+}
+
+#[test]
+fn test_bad_magic() {
+    let mut bytes = BYTES.to_owned();
+    bytes[0] = b'X';
+    match Model::from_bytes(&bytes) {
+        Err(Error::BadMagic { offset: 0 }) => (),
+        res => panic!("unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn test_unexpected_eof() {
+    let err = Model::from_reader(&BYTES[..100]).unwrap_err();
+    match err {
+        Error::UnexpectedEof {
+            section: Section::Vertices,
+            offset,
+        } => assert!(offset <= 100),
+        err => panic!("unexpected error {:?}", err),
+    }
+    let _: Box<dyn std::error::Error + Send + Sync> = Box::new(err);
+}
+
+/// The file up to the end of the joints, followed by a comments section with
+/// the given comment counts.
+fn with_comment_counts(counts: &[i32]) -> Vec<u8> {
+    let mut bytes = BYTES[..joints_end()].to_owned();
+    bytes.extend_from_slice(&1i32.to_le_bytes());
+    for count in counts {
+        bytes.extend_from_slice(&count.to_le_bytes());
+    }
+    bytes
+}
+
+#[test]
+fn test_hostile_counts() {
+    let bytes = with_comment_counts(&[i32::MAX]);
+    match Model::from_bytes(&bytes).unwrap_err() {
+        Error::LimitExceeded {
+            section: Section::Comments,
+            limit: Limit::Comments,
+            ..
+        } => {}
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    let options = ParseOptions {
+        limits: Limits::unlimited(),
+        ..Default::default()
+    };
+    match Model::from_bytes_with(&bytes, options).unwrap_err() {
+        Error::UnexpectedEof {
+            section: Section::Comments,
+            ..
+        } => {}
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    let bytes = with_comment_counts(&[-1]);
+    match Model::from_bytes(&bytes).unwrap_err() {
+        Error::InvalidCount {
+            section: Section::Comments,
+            count: -1,
+            offset,
+        } => assert_eq!(offset, joints_end() as u64 + 4),
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    let bytes = with_comment_counts(&[0, -1]);
+    match Model::from_bytes(&bytes).unwrap_err() {
+        Error::InvalidCount {
+            section: Section::Comments,
+            count: -1,
+            offset,
+        } => assert_eq!(offset, joints_end() as u64 + 8),
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+fn test_comment_length_limit() {
+    let mut bytes = with_comment_counts(&[1, 0, 100]);
+    bytes.extend_from_slice(&[b'x'; 100]);
+    bytes.extend_from_slice(&[0; 12]);
+    assert!(Model::from_bytes(&bytes).is_ok());
+
+    let mut options = ParseOptions::default();
+    options.limits.max_comment_length = 10;
+    match Model::from_bytes_with(&bytes, options).unwrap_err() {
+        Error::LimitExceeded {
+            limit: Limit::CommentLength,
+            offset,
+            ..
+        } => assert_eq!(offset, joints_end() as u64 + 12),
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+fn test_recover() {
+    let model = Model::from_bytes(BYTES).unwrap();
+    let len = joints_end();
+    let recovery = Model::from_reader_recover(&BYTES[..len - 2]);
+    assert_eq!(recovery.section, Some(Section::Joints));
+    match recovery.error.unwrap() {
+        Error::UnexpectedEof {
+            section: Section::Joints,
+            ..
+        } => {}
+        err => panic!("unexpected error {:?}", err),
+    }
+    assert_eq!(recovery.model.vertices, model.vertices);
+    assert_eq!(recovery.model.triangles, model.triangles);
+    assert_eq!(recovery.model.groups, model.groups);
+    assert_eq!(recovery.model.materials, model.materials);
+    assert_eq!(recovery.model.key_frame_data, model.key_frame_data);
+    assert!(recovery.model.comments.is_none());
+
+    let recovery = Model::from_bytes_recover(&BYTES[..1000]);
+    assert_eq!(recovery.section, Some(Section::Vertices));
+    assert_eq!(recovery.model.header, model.header);
+    assert!(recovery.model.vertices.is_empty());
+
+    let recovery = Model::from_bytes_recover(BYTES);
+    assert!(recovery.error.is_none());
+    assert_eq!(recovery.model, model);
+}
+
+#[test]
+fn test_version_3() {
+    let len = joints_end();
+    let mut bytes = BYTES.to_owned();
+    bytes[10] = 3;
+
+    let model = Model::from_bytes(&bytes[..len]).unwrap();
+    assert_eq!(model.header.version, 3);
+    assert!(model.comments.is_none());
+    assert!(model.unknown_trailer.is_empty());
+
+    let model = Model::from_bytes(&bytes).unwrap();
+    assert!(model.comments.is_none());
+    assert_eq!(model.unknown_trailer, &bytes[len..]);
+    assert_eq!(model.to_bytes().unwrap(), bytes);
+
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    model.header.version = 3;
+    match model.to_bytes().unwrap_err() {
+        Error::UnsupportedSection {
+            section: Section::Comments,
+            version: 3,
+        } => {}
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+fn test_decode_fields() {
+    let model = Model::from_bytes(BYTES).unwrap();
+
+    assert_eq!(model.header.version, 4);
+
+    assert_eq!(model.vertices.len(), 2709);
+    let vertex = &model.vertices[0];
+    assert_eq!(vertex.flags, Flags::SELECTED);
+    assert_eq!(vertex.vertex, [-3.8615417, 15.250204, 9.339245]);
+    assert_eq!(vertex.bone_id, -1);
+    assert_eq!(vertex.reference_count, 5);
+
+    assert_eq!(model.triangles.len(), 4756);
+    let triangle = &model.triangles[0];
+    assert_eq!(triangle.flags, Flags::SELECTED);
+    assert_eq!(triangle.vertex_indices, [0, 1, 2]);
+    assert_eq!(triangle.vertex_normals, [[0.0, 0.0, 1.0]; 3]);
+    assert_eq!(triangle.s, [0.0, 0.0, 1.0]);
+    assert_eq!(triangle.t, [0.0, 1.0, 0.0]);
+    assert_eq!(triangle.smoothing_group, 1);
+    assert_eq!(triangle.group_index, 0);
+
+    assert_eq!(model.groups.len(), 76);
+    let group = &model.groups[0];
+    assert_eq!(group.flags, Flags::SELECTED);
+    assert_eq!(group.name, "Box07");
+    assert_eq!(group.triangle_indices.len(), 12);
+
+    assert_eq!(model.materials.len(), 1);
+    let material = &model.materials[0];
+    assert_eq!(material.name, "POA5");
+    assert_eq!(material.ambient, [0.2, 0.2, 0.2, 1.0]);
+    assert_eq!(material.diffuse, [0.8, 0.8, 0.8, 1.0]);
+    assert_eq!(material.specular, [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(material.emissive, [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(material.shininess, 0.0);
+    assert_eq!(material.transparency, 1.0);
+    assert_eq!(material.mode, 0);
+    assert_eq!(material.texture.to_str(), Some("POA5.tga"));
+    assert_eq!(material.alphamap.to_str(), Some(""));
+
+    assert_eq!(model.key_frame_data.animation_fps, 24.0);
+    assert_eq!(model.key_frame_data.current_time, 1.0);
+    assert_eq!(model.key_frame_data.total_frames, 1);
+
+    assert!(model.joints.is_empty());
+}
+
+fn lenient() -> ParseOptions {
+    ParseOptions {
+        strict: false,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_lenient_flags() {
+    // Flags of the first vertex.
+    let mut bytes = BYTES.to_owned();
+    bytes[16] = 0xf1;
+
+    match Model::from_bytes(&bytes) {
+        Err(Error::InvalidFlags {
+            section: Section::Vertices,
+            index: 0,
+            bits: 0xf1,
+            offset: 16,
+        }) => (),
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    let (model, warnings) = Model::from_bytes_with(&bytes, lenient()).unwrap();
+    assert_eq!(model.vertices[0].flags.bits(), 0xf1);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::InvalidFlags { bits: 0xf1 });
+}
+
+#[test]
+fn test_triangle_flags_high_byte() {
+    // High byte of the flags of the first triangle.
+    let offset = 14 + 2 + 15 * 2709 + 2;
+    let mut bytes = BYTES.to_owned();
+    bytes[offset + 1] = 0x01;
+
+    match Model::from_bytes(&bytes) {
+        Err(Error::InvalidFlags {
+            section: Section::Triangles,
+            index: 0,
+            bits: 0x0101,
+            ..
+        }) => (),
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    let (model, warnings) = Model::from_bytes_with(&bytes, lenient()).unwrap();
+    assert!(model.triangles[0].flags.contains(Flags::SELECTED));
+    assert_eq!(model.triangles[0].flags.bits(), 0x0101);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::InvalidFlags { bits: 0x0101 });
+    assert_eq!(model.to_bytes().unwrap(), bytes);
+
+    let mut model = model;
+    model.triangles[0].flags = Flags::HIDDEN;
+    assert_eq!(model.to_bytes().unwrap()[offset..offset + 2], [2, 0]);
+}
+
+// Offset of the name of the first group.
+const GROUP_NAME: usize = 14 + 2 + 15 * 2709 + 2 + 70 * 4756 + 2 + 1;
+
+#[test]
+fn test_lenient_utf8() {
+    let mut bytes = BYTES.to_owned();
+    bytes[GROUP_NAME] = 0xff;
+
+    let options = ParseOptions {
+        encoding: &Utf8,
+        ..Default::default()
+    };
+    match Model::from_bytes_with(&bytes, options) {
+        Err(Error::InvalidText {
+            section: Section::Groups,
+            index: 0,
+            ..
+        }) => (),
+        res => panic!("unexpected result {:?}", res),
+    }
+
+    let options = ParseOptions {
+        encoding: &Utf8,
+        ..lenient()
+    };
+    let (model, warnings) = Model::from_reader_with(&bytes[..], options).unwrap();
+    assert_eq!(model.groups[0].name, "\u{fffd}ox07");
+    assert_eq!(model.groups[0].raw_name[..5], b"\xffox07"[..]);
+    assert_eq!(warnings[0].kind, WarningKind::InvalidText);
+}
+
+#[test]
+fn test_windows_1252() {
+    let mut bytes = BYTES.to_owned();
+    bytes[GROUP_NAME] = 0x80;
+    bytes[GROUP_NAME + 1] = 0xe9;
+
+    let model = Model::from_bytes(&bytes).unwrap();
+    assert_eq!(model.groups[0].name, "\u{20ac}\u{e9}x07");
+}
+
+#[test]
+fn test_lenient_sub_version() {
+    let len = joints_end();
+    let mut bytes = BYTES.to_owned();
+    bytes[len] = 7;
+
+    assert!(Model::from_bytes(&bytes).is_err());
+
+    let (model, warnings) = Model::from_bytes_with(&bytes, lenient()).unwrap();
+    assert!(model.comments.is_none());
+    assert!(model.model_ex_info.is_none());
+    assert_eq!(
+        warnings,
+        vec![Warning {
+            section: Section::Comments,
+            index: 0,
+            offset: len as u64,
+            kind: WarningKind::UnsupportedSubVersion { sub_version: 7 },
+        }]
+    );
+}
+
+#[cfg(feature = "encoding_rs")]
+#[test]
+fn test_shift_jis() {
+    let mut bytes = BYTES.to_owned();
+    bytes[GROUP_NAME..GROUP_NAME + 4].copy_from_slice(b"\x82\xa0\x82\xa2");
+
+    let options = ParseOptions {
+        encoding: &ms3d::ShiftJis,
+        ..Default::default()
+    };
+    let (model, _) = Model::from_bytes_with(&bytes, options).unwrap();
+    assert_eq!(model.groups[0].name, "\u{3042}\u{3044}7");
+
+    use ms3d::TextEncoding;
+    assert!(ms3d::ShiftJis.is_valid(b"\x82\xa0\x82\xa2"));
+    assert!(!ms3d::ShiftJis.is_valid(b"\x82\xa0\x82"));
+}
@@ -0,0 +1,97 @@
+extern crate ms3d;
+
+use ms3d::{
+    Diagnostic, DiagnosticKind, Flags, Joint, JointEx, KeyFramePos, Model, ParseOptions, Section,
+};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+#[test]
+fn test_diagnostics() {
+    let (model, diagnostics) = Model::from_bytes_diagnostic(BYTES).unwrap();
+    assert_eq!(model, Model::from_bytes(BYTES).unwrap());
+    assert_eq!(diagnostics, vec![]);
+}
+
+#[test]
+fn test_diagnostics_oddities() {
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    let reference_count = model.vertices[2].reference_count;
+    model.vertices[2].reference_count += 1;
+    model.groups[1].name.clear();
+    model.key_frame_data.total_frames = 0;
+    model.joints.push(Joint {
+        flags: Flags::empty(),
+        name: "root".to_owned(),
+        raw_name: Vec::new(),
+        parent_name: String::new(),
+        raw_parent_name: Vec::new(),
+        rotation: [0.0; 3],
+        position: [0.0; 3],
+        key_frames_rot: Vec::new(),
+        key_frames_trans: vec![KeyFramePos {
+            time: 2.0,
+            position: [0.0; 3],
+        }],
+    });
+    model
+        .joint_ex_info
+        .as_mut()
+        .unwrap()
+        .joint_ex
+        .push(JointEx { color: [0.0; 3] });
+
+    let bytes = model.to_bytes().unwrap();
+    let (_, diagnostics) = Model::from_reader_diagnostic(&bytes[..]).unwrap();
+    assert_eq!(
+        diagnostics,
+        vec![
+            Diagnostic {
+                section: Section::Vertices,
+                index: 2,
+                kind: DiagnosticKind::ReferenceCountMismatch {
+                    reference_count: reference_count + 1,
+                    actual: reference_count,
+                },
+            },
+            Diagnostic {
+                section: Section::Groups,
+                index: 1,
+                kind: DiagnosticKind::EmptyGroupName,
+            },
+            Diagnostic {
+                section: Section::Joints,
+                index: 0,
+                kind: DiagnosticKind::NoFrames,
+            },
+        ]
+    );
+    assert_eq!(
+        diagnostics[2].to_string(),
+        "element 0 of joints has key frames, but total frames is 0"
+    );
+
+    // The high byte of the first triangle's flags, which lenient mode keeps.
+    let mut lenient_bytes = bytes.clone();
+    lenient_bytes[14 + 2 + 15 * 2709 + 3] = 1;
+    let options = ParseOptions {
+        strict: false,
+        ..Default::default()
+    };
+    let (_, warnings, lenient) =
+        Model::from_bytes_diagnostic_with(&lenient_bytes, options).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(lenient, diagnostics);
+
+    let mut model = Model::from_bytes(&bytes).unwrap();
+    model.key_frame_data.total_frames = 24;
+    let (_, diagnostics) = Model::from_bytes_diagnostic(&model.to_bytes().unwrap()).unwrap();
+    assert_eq!(
+        diagnostics.last(),
+        Some(&Diagnostic {
+            section: Section::Joints,
+            index: 0,
+            kind: DiagnosticKind::KeyFrameOutOfRange { time: 2.0 },
+        })
+    );
+}
@@ -0,0 +1,88 @@
+extern crate ms3d;
+
+use std::path::PathBuf;
+
+use ms3d::{Model, ModelBuilder, RepairOptions};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+#[test]
+fn test_remove_geometry() {
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    let num_vertices = model.vertices.len();
+    let num_triangles = model.triangles.len();
+    let num_groups = model.groups.len();
+    let group_triangles = model.groups[1].triangle_indices.len();
+    let last_group = model.groups[num_groups - 1].clone();
+
+    model.remove_group(1);
+    assert_eq!(model.groups.len(), num_groups - 1);
+    assert_eq!(model.triangles.len(), num_triangles - group_triangles);
+    assert_eq!(model.groups[num_groups - 2].name, last_group.name);
+    assert_eq!(model.validate(), vec![]);
+
+    let used = model
+        .triangles
+        .iter()
+        .filter(|triangle| triangle.vertex_indices.contains(&0))
+        .count();
+    let num_triangles = model.triangles.len();
+    model.remove_vertices(&[0, 1]);
+    assert_eq!(model.vertices.len(), num_vertices - 2);
+    assert!(model.triangles.len() <= num_triangles - used);
+    assert_eq!(
+        model.vertex_ex_info.as_ref().unwrap().len(),
+        num_vertices - 2
+    );
+    assert_eq!(model.validate(), vec![]);
+
+    let options = RepairOptions {
+        remove_invalid_triangle_indices: false,
+        remove_invalid_groups: false,
+        remove_degenerate_triangles: false,
+        remove_invalid_triangles: false,
+        clamp_indices: false,
+        fix_group_indices: false,
+        recompute_reference_counts: true,
+    };
+    assert!(model.repair(&options).is_empty());
+}
+
+#[test]
+fn test_remove_joint_and_material() {
+    let mut builder = ModelBuilder::new();
+    let root = builder.add_joint("root", None).unwrap();
+    let arm = builder.add_joint("arm", Some(root)).unwrap();
+    let hand = builder.add_joint("hand", Some(arm)).unwrap();
+    builder.add_vertex([0.0; 3], Some(arm)).unwrap();
+    builder.add_vertex([0.0; 3], Some(hand)).unwrap();
+    let first = builder.add_material("first", PathBuf::new()).unwrap();
+    let second = builder.add_material("second", PathBuf::new()).unwrap();
+    builder.add_group("a", Some(first)).unwrap();
+    builder.add_group("b", Some(second)).unwrap();
+    let mut model = builder.build();
+
+    model.remove_joint(arm);
+    assert_eq!(model.joints.len(), 2);
+    // The child of the removed joint is given the removed joint's parent.
+    assert_eq!(model.joints[1].name, "hand");
+    assert_eq!(model.joints[1].parent_name, "root");
+    assert_eq!(model.joints[1].raw_parent_name, model.joints[0].raw_name);
+    assert_eq!(model.vertices[0].bone_id, -1);
+    assert_eq!(model.vertices[1].bone_id, 1);
+    assert_eq!(model.joint_ex_info.as_ref().unwrap().joint_ex.len(), 2);
+
+    let mut without_root = model.clone();
+    without_root.remove_joint(root);
+    assert_eq!(without_root.joints[0].parent_name, "");
+    assert_eq!(
+        without_root.joints[0].raw_parent_name,
+        model.joints[0].raw_parent_name
+    );
+
+    model.remove_material(first);
+    assert_eq!(model.materials.len(), 1);
+    assert_eq!(model.groups[0].material_index, -1);
+    assert_eq!(model.groups[1].material_index, 0);
+    assert_eq!(model.validate(), vec![]);
+}
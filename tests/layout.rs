@@ -0,0 +1,48 @@
+extern crate ms3d;
+
+use std::io::Cursor;
+
+use ms3d::{layout, layout_seek, Model};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+#[test]
+fn test_layout() {
+    let model = Model::from_bytes(BYTES).unwrap();
+    let layout = layout(BYTES).unwrap();
+
+    assert_eq!(layout.header, 0..14);
+    assert_eq!(layout.vertices.range.start, 14);
+    assert_eq!(layout.vertices.records.len(), model.vertices.len());
+    assert_eq!(layout.triangles.range.start, layout.vertices.range.end);
+    assert_eq!(layout.groups.records.len(), model.groups.len());
+    for (group, range) in model.groups.iter().zip(&layout.groups.records) {
+        assert_eq!(
+            range.end - range.start,
+            36 + group.triangle_indices.len() as u64 * 2
+        );
+    }
+    for pair in layout.groups.records.windows(2) {
+        assert_eq!(pair[0].end, pair[1].start);
+    }
+    for range in &layout.materials.records {
+        assert_eq!(range.end - range.start, 361);
+    }
+    assert_eq!(layout.key_frame_data.start, layout.materials.range.end);
+    assert_eq!(layout.joints.records.len(), model.joints.len());
+    assert_eq!(
+        layout.unknown_trailer,
+        BYTES.len() as u64..BYTES.len() as u64
+    );
+
+    assert_eq!(layout_seek(Cursor::new(BYTES)).unwrap(), layout);
+}
+
+#[test]
+fn test_layout_trailer() {
+    let mut bytes = BYTES.to_vec();
+    bytes.extend_from_slice(b"extra");
+    let layout = layout(&bytes[..]).unwrap();
+    let len = BYTES.len() as u64;
+    assert_eq!(layout.unknown_trailer, len..len + 5);
+}
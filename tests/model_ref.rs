@@ -0,0 +1,148 @@
+extern crate ms3d;
+
+use ms3d::{
+    Comment, Error, Model, ModelRef, ParseOptions, Section, Utf8, VertexExInfo, VertexExInfoRef,
+    WarningKind,
+};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+#[test]
+fn test_model_ref() {
+    let model = Model::from_bytes(BYTES).unwrap();
+    let model_ref = ModelRef::new(BYTES).unwrap();
+
+    assert_eq!(model_ref.to_model().unwrap(), model);
+
+    assert_eq!(model_ref.vertices().len(), model.vertices.len());
+    assert!(model_ref.vertices().eq(model.vertices.iter().cloned()));
+    assert_eq!(
+        model_ref.vertices().nth(100),
+        Some(model.vertices[100].clone())
+    );
+    assert!(model_ref.triangles().eq(model.triangles.iter().cloned()));
+
+    assert_eq!(model_ref.groups().len(), model.groups.len());
+    for (group_ref, group) in model_ref.groups().zip(&model.groups) {
+        assert_eq!(group_ref.flags(), group.flags);
+        assert_eq!(group_ref.name(), group.name);
+        assert_eq!(group_ref.name_bytes(), group.name.as_bytes());
+        assert!(group_ref
+            .triangle_indices()
+            .eq(group.triangle_indices.iter().cloned()));
+        assert_eq!(group_ref.material_index(), group.material_index);
+    }
+
+    for (material_ref, material) in model_ref.materials().zip(&model.materials) {
+        assert_eq!(material_ref.name(), material.name);
+        assert_eq!(material_ref.diffuse(), material.diffuse);
+        assert_eq!(
+            material_ref.texture_bytes(),
+            material.texture.to_str().unwrap().as_bytes()
+        );
+    }
+
+    assert_eq!(model_ref.joints().len(), model.joints.len());
+
+    let comments = model.comments.as_ref().unwrap();
+    let comments_ref = model_ref.comments().unwrap();
+    assert_eq!(
+        comments_ref.group_comments().len(),
+        comments.group_comments.len()
+    );
+    assert_eq!(
+        comments_ref.model_comment().map(|c| c.comment()),
+        comments.model_comment.as_ref().map(|c| c.comment.clone())
+    );
+
+    match (model_ref.vertex_ex_info(), &model.vertex_ex_info) {
+        (Some(VertexExInfoRef::SubVersion1(r)), &Some(VertexExInfo::SubVersion1(ref v))) => {
+            assert!(r.eq(v.iter().cloned()))
+        }
+        (Some(VertexExInfoRef::SubVersion2(r)), &Some(VertexExInfo::SubVersion2(ref v))) => {
+            assert!(r.eq(v.iter().cloned()))
+        }
+        (Some(VertexExInfoRef::SubVersion3(r)), &Some(VertexExInfo::SubVersion3(ref v))) => {
+            assert!(r.eq(v.iter().cloned()))
+        }
+        (None, &None) => (),
+        _ => panic!("vertex ex info mismatch"),
+    }
+    assert_eq!(model_ref.model_ex_info(), model.model_ex_info.as_ref());
+}
+
+#[test]
+fn test_model_ref_truncated() {
+    assert!(ModelRef::new(&BYTES[..BYTES.len() / 2]).is_err());
+}
+
+#[test]
+fn test_model_ref_invalid_text() {
+    let model = Model::from_bytes(BYTES).unwrap();
+    let name = &model.materials[0].raw_name;
+    let offset = BYTES
+        .windows(name.len())
+        .position(|window| window == &name[..])
+        .unwrap();
+    let mut bytes = BYTES.to_owned();
+    bytes[offset] = 0xff;
+
+    let options = ParseOptions {
+        encoding: &Utf8,
+        ..Default::default()
+    };
+    match ModelRef::new_with(&bytes, options.clone()) {
+        Err(Error::InvalidText {
+            section: Section::Materials,
+            index: 0,
+            ..
+        }) => (),
+        res => panic!("unexpected result {:?}", res.map(|_| ())),
+    }
+
+    let options = ParseOptions {
+        strict: false,
+        ..options
+    };
+    let (_, warnings) = ModelRef::new_with(&bytes, options).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::InvalidText);
+}
+
+#[test]
+fn test_model_ref_invalid_comment() {
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    model.comments.as_mut().unwrap().model_comment = Some(Comment {
+        index: 0,
+        comment: "comment".to_owned(),
+        raw_comment: b"comment".to_vec(),
+    });
+    let mut bytes = model.to_bytes().unwrap();
+    let offset = bytes
+        .windows(7)
+        .position(|window| window == b"comment")
+        .unwrap();
+    bytes[offset] = 0xff;
+
+    let options = ParseOptions {
+        encoding: &Utf8,
+        ..Default::default()
+    };
+    assert!(Model::from_bytes_with(&bytes, options.clone()).is_err());
+    match ModelRef::new_with(&bytes, options.clone()) {
+        Err(Error::InvalidText {
+            section: Section::Comments,
+            offset: error_offset,
+            ..
+        }) => assert_eq!(error_offset, offset as u64),
+        res => panic!("unexpected result {:?}", res.map(|_| ())),
+    }
+
+    let options = ParseOptions {
+        strict: false,
+        ..options
+    };
+    let (_, warnings) = ModelRef::new_with(&bytes, options).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::InvalidText);
+}
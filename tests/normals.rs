@@ -0,0 +1,91 @@
+extern crate ms3d;
+
+use ms3d::{Model, ModelBuilder, NormalOptions, NormalWeighting};
+
+fn assert_close(actual: [f32; 3], expected: [f32; 3]) {
+    for axis in 0..3 {
+        assert!(
+            (actual[axis] - expected[axis]).abs() < 1e-5,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+}
+
+/// Two right triangles meeting at a right angle along the edge from `a` to
+/// `b`, the second one twice the area of the first.
+fn fold(smoothing_group: u8) -> Model {
+    let mut builder = ModelBuilder::new();
+    let group = builder.add_group("fold", None).unwrap();
+    let a = builder.add_vertex([0.0, 0.0, 0.0], None).unwrap();
+    let b = builder.add_vertex([1.0, 0.0, 0.0], None).unwrap();
+    let c = builder.add_vertex([0.0, 1.0, 0.0], None).unwrap();
+    let d = builder.add_vertex([0.0, 0.0, 2.0], None).unwrap();
+    let normals = [[0.0; 3]; 3];
+    let uvs = [[0.0; 2]; 3];
+    builder
+        .add_triangle(group, [a, b, c], normals, uvs)
+        .unwrap();
+    builder
+        .add_triangle(group, [a, d, b], normals, uvs)
+        .unwrap();
+    let mut model = builder.build();
+    for triangle in &mut model.triangles {
+        triangle.smoothing_group = smoothing_group;
+    }
+    model
+}
+
+#[test]
+fn test_flat_normals() {
+    let mut model = fold(0);
+    model.recompute_normals(&NormalOptions::default());
+    assert_eq!(model.triangles[0].vertex_normals, [[0.0, 0.0, 1.0]; 3]);
+    assert_eq!(model.triangles[1].vertex_normals, [[0.0, 1.0, 0.0]; 3]);
+}
+
+#[test]
+fn test_smooth_normals() {
+    let mut model = fold(1);
+    model.recompute_normals(&NormalOptions::default());
+    let half = 0.5f32.sqrt();
+    assert_close(model.triangles[0].vertex_normals[0], [0.0, half, half]);
+    assert_close(model.triangles[1].vertex_normals[0], [0.0, half, half]);
+    assert_close(model.triangles[0].vertex_normals[2], [0.0, 0.0, 1.0]);
+    assert_close(model.triangles[1].vertex_normals[1], [0.0, 1.0, 0.0]);
+
+    // A degenerate triangle takes the normals of its neighbours, or zero
+    // where it has none.
+    let mut degenerate = model.triangles[0].clone();
+    degenerate.vertex_indices = [0, 2, 2];
+    model.triangles.push(degenerate.clone());
+    model.recompute_normals(&NormalOptions::default());
+    assert_close(model.triangles[2].vertex_normals[0], [0.0, half, half]);
+    assert_close(model.triangles[2].vertex_normals[1], [0.0, 0.0, 1.0]);
+    degenerate.smoothing_group = 0;
+    model.triangles[2] = degenerate;
+    model.recompute_normals(&NormalOptions::default());
+    assert_eq!(model.triangles[2].vertex_normals, [[0.0; 3]; 3]);
+
+    let mut model = fold(1);
+    let options = NormalOptions {
+        weighting: NormalWeighting::Area,
+    };
+    model.recompute_normals(&options);
+    let fifth = 0.2f32.sqrt();
+    assert_close(
+        model.triangles[0].vertex_normals[0],
+        [0.0, 2.0 * fifth, fifth],
+    );
+    assert_close(
+        model.triangles[1].vertex_normals[2],
+        [0.0, 2.0 * fifth, fifth],
+    );
+
+    // Triangles in different smoothing groups are not averaged.
+    model.triangles[1].smoothing_group = 2;
+    model.recompute_normals(&options);
+    assert_close(model.triangles[0].vertex_normals[0], [0.0, 0.0, 1.0]);
+    assert_close(model.triangles[1].vertex_normals[0], [0.0, 1.0, 0.0]);
+}
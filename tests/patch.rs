@@ -0,0 +1,81 @@
+extern crate ms3d;
+
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use ms3d::{Error, Model, Patcher, Section};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+#[test]
+fn test_patch() {
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    let mut patcher = Patcher::new(Cursor::new(BYTES.to_vec())).unwrap();
+
+    model.materials[0].texture = PathBuf::from("textures\\POA6.tga");
+    patcher.set_material(0, &model.materials[0]).unwrap();
+    patcher.set_group_name(1, "Head").unwrap();
+
+    let bytes = patcher.into_inner().into_inner();
+    assert_eq!(bytes.len(), BYTES.len());
+    let patched = Model::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        patched.materials[0].texture,
+        PathBuf::from("textures\\POA6.tga")
+    );
+    assert_eq!(patched.materials[0].name, model.materials[0].name);
+    assert_eq!(patched.groups[1].name, "Head");
+    assert_eq!(patched.groups[0], model.groups[0]);
+    assert_eq!(patched.groups[2], model.groups[2]);
+}
+
+#[test]
+fn test_patch_errors() {
+    let mut patcher = Patcher::new(Cursor::new(BYTES.to_vec())).unwrap();
+
+    match patcher.set_group_name(0, &"x".repeat(33)) {
+        Err(Error::StringTooLong {
+            section: Section::Groups,
+            index: 0,
+            len: 33,
+            max: 32,
+        }) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+    match patcher.set_joint_name(0, "root") {
+        Err(Error::IndexOutOfRange {
+            section: Section::Joints,
+            index: 0,
+            len: 0,
+        }) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+    match patcher.set_joint_color(0, [1.0; 3]) {
+        Err(Error::IndexOutOfRange {
+            section: Section::JointExInfo,
+            ..
+        }) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    assert_eq!(patcher.into_inner().into_inner(), BYTES);
+}
+
+#[test]
+fn test_patch_missing_section() {
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    model.comments = None;
+    model.vertex_ex_info = None;
+    model.joint_ex_info = None;
+    model.model_ex_info = None;
+    let mut patcher = Patcher::new(Cursor::new(model.to_bytes().unwrap())).unwrap();
+
+    match patcher.set_joint_color(0, [1.0; 3]) {
+        Err(
+            err @ Error::MissingSection {
+                section: Section::JointExInfo,
+            },
+        ) => assert_eq!(err.to_string(), "joint extra info section is missing"),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
@@ -0,0 +1,51 @@
+extern crate ms3d;
+
+use std::io::Cursor;
+
+use ms3d::{probe, probe_seek, Error, Model, Section};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+#[test]
+fn test_probe() {
+    let model = Model::from_bytes(BYTES).unwrap();
+    let summary = probe(BYTES).unwrap();
+
+    assert_eq!(summary.version, model.header.version);
+    assert_eq!(summary.num_vertices, model.vertices.len());
+    assert_eq!(summary.num_triangles, model.triangles.len());
+    assert_eq!(
+        summary.group_names,
+        model
+            .groups
+            .iter()
+            .map(|g| g.name.clone())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(summary.material_names, vec!["POA5".to_owned()]);
+    assert!(summary.joint_names.is_empty());
+    assert_eq!(summary.total_frames, 1);
+    assert_eq!(summary.has_vertex_weights, model.vertex_ex_info.is_some());
+
+    assert_eq!(probe_seek(Cursor::new(BYTES)).unwrap(), summary);
+}
+
+#[test]
+fn test_probe_truncated() {
+    // Ends in the middle of the triangles.
+    let bytes = &BYTES[..50000];
+    match probe(bytes) {
+        Err(Error::UnexpectedEof {
+            section: Section::Triangles,
+            ..
+        }) => (),
+        res => panic!("unexpected result {:?}", res),
+    }
+    match probe_seek(Cursor::new(bytes)) {
+        Err(Error::UnexpectedEof {
+            section: Section::Triangles,
+            ..
+        }) => (),
+        res => panic!("unexpected result {:?}", res),
+    }
+}
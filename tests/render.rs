@@ -0,0 +1,75 @@
+extern crate ms3d;
+
+use ms3d::{Indices, Model, ModelBuilder, VertexExInfo};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+#[test]
+fn test_render_meshes() {
+    let model = Model::from_bytes(BYTES).unwrap();
+    let meshes = model.to_render_meshes();
+    assert_eq!(meshes.len(), model.groups.len());
+
+    for (mesh, group) in meshes.iter().zip(&model.groups) {
+        assert_eq!(mesh.name, group.name);
+        assert_eq!(mesh.indices.len(), group.triangle_indices.len() * 3);
+        assert!(mesh.vertices.len() <= mesh.indices.len());
+        match mesh.indices {
+            Indices::U16(_) => {}
+            Indices::U32(_) => panic!("expected 16-bit indices"),
+        }
+        for (i, &triangle_index) in group.triangle_indices.iter().enumerate() {
+            let triangle = &model.triangles[triangle_index as usize];
+            for corner in 0..3 {
+                let index = mesh.indices.get(i * 3 + corner).unwrap();
+                let vertex = &mesh.vertices[index as usize];
+                let source = triangle.vertex_indices[corner] as usize;
+                assert_eq!(vertex.position, model.vertices[source].vertex);
+                assert_eq!(vertex.normal, triangle.vertex_normals[corner]);
+                assert_eq!(vertex.uv, [triangle.s[corner], triangle.t[corner]]);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_render_meshes_split_and_weights() {
+    let mut builder = ModelBuilder::new();
+    let root = builder.add_joint("root", None).unwrap();
+    let arm = builder.add_joint("arm", Some(root)).unwrap();
+    let group = builder.add_group("quad", None).unwrap();
+    let a = builder.add_vertex([0.0, 0.0, 0.0], Some(root)).unwrap();
+    let b = builder.add_vertex([1.0, 0.0, 0.0], None).unwrap();
+    let c = builder.add_vertex([1.0, 1.0, 0.0], None).unwrap();
+    let d = builder.add_vertex([0.0, 1.0, 0.0], None).unwrap();
+    let normals = [[0.0, 0.0, 1.0]; 3];
+    let uvs = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]];
+    builder
+        .add_triangle(group, [a, b, c], normals, uvs)
+        .unwrap();
+    let uvs = [[0.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+    builder
+        .add_triangle(group, [a, c, d], normals, uvs)
+        .unwrap();
+    // The same corners as the first triangle, but with a different texture
+    // coordinate for `a`.
+    let uvs = [[0.5, 0.5], [1.0, 0.0], [1.0, 1.0]];
+    builder
+        .add_triangle(group, [a, b, c], normals, uvs)
+        .unwrap();
+    let mut model = builder.build();
+    if let Some(VertexExInfo::SubVersion2(ref mut vertex_ex)) = model.vertex_ex_info {
+        vertex_ex[0].bone_ids = [arm as i8, -1, -1];
+        vertex_ex[0].weights = [75, 25, 0];
+    }
+
+    let meshes = model.to_render_meshes();
+    assert_eq!(meshes.len(), 1);
+    let mesh = &meshes[0];
+    assert_eq!(mesh.vertices.len(), 5);
+    assert_eq!(mesh.indices, Indices::U16(vec![0, 1, 2, 0, 2, 3, 4, 1, 2]));
+    assert_eq!(mesh.vertices[0].bone_ids, [0, 1, -1, -1]);
+    assert_eq!(mesh.vertices[0].weights, [0.75, 0.25, 0.0, 0.0]);
+    assert_eq!(mesh.vertices[1].bone_ids, [-1; 4]);
+    assert_eq!(mesh.vertices[1].weights, [0.0; 4]);
+}
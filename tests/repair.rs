@@ -0,0 +1,83 @@
+extern crate ms3d;
+
+use ms3d::{Model, RepairOptions};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+#[test]
+fn test_repair_clean() {
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    let original = model.clone();
+    assert!(model.repair(&RepairOptions::default()).is_empty());
+    assert_eq!(model, original);
+}
+
+/// A copy of the model with one degenerate triangle, one triangle with a
+/// missing vertex, a group which lists a missing triangle, an invalid material
+/// index, an invalid bone id and a triangle with the wrong group index.
+fn broken_model() -> Model {
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    let moved = model.groups[1].triangle_indices[0] as usize;
+    let first = model.groups[0].triangle_indices[0] as usize;
+    model.triangles[first].vertex_indices[1] = model.triangles[first].vertex_indices[0];
+    let second = model.groups[0].triangle_indices[1] as usize;
+    model.triangles[second].vertex_indices[2] = 9999;
+    model.groups[2].triangle_indices.push(10000);
+    model.groups[3].material_index = 5;
+    model.vertices[0].bone_id = 3;
+    model.triangles[moved].group_index = 0;
+    model
+}
+
+#[test]
+fn test_repair() {
+    let original = Model::from_bytes(BYTES).unwrap();
+    let group_len = original.groups[2].triangle_indices.len();
+    let mut model = broken_model();
+
+    let report = model.repair(&RepairOptions::default());
+    assert_eq!(report.removed_triangle_indices, 0);
+    assert_eq!(report.removed_groups, 1);
+    assert_eq!(report.removed_orphan_triangles, group_len);
+    assert_eq!(report.removed_degenerate_triangles, 1);
+    assert_eq!(report.removed_invalid_triangles, 1);
+    assert_eq!(report.clamped_material_indices, 1);
+    assert_eq!(report.clamped_bone_ids, 1);
+    assert_eq!(report.updated_group_indices, 1);
+    assert!(report.updated_reference_counts > 0);
+
+    assert_eq!(model.groups.len(), original.groups.len() - 1);
+    assert_eq!(
+        model.triangles.len(),
+        original.triangles.len() - 2 - group_len
+    );
+    assert_eq!(model.groups[2].material_index, -1);
+    assert_eq!(model.vertices[0].bone_id, -1);
+    assert!(model.validate().is_empty());
+    assert!(model.repair(&RepairOptions::default()).is_empty());
+}
+
+#[test]
+fn test_repair_triangle_indices() {
+    let original = Model::from_bytes(BYTES).unwrap();
+    let mut model = broken_model();
+
+    let options = RepairOptions {
+        remove_invalid_triangle_indices: true,
+        ..Default::default()
+    };
+    let report = model.repair(&options);
+    assert_eq!(report.removed_triangle_indices, 1);
+    assert_eq!(report.removed_groups, 0);
+    assert_eq!(report.removed_orphan_triangles, 0);
+    assert_eq!(report.removed_invalid_triangles, 1);
+
+    assert_eq!(model.groups.len(), original.groups.len());
+    assert_eq!(
+        model.groups[2].triangle_indices.len(),
+        original.groups[2].triangle_indices.len()
+    );
+    assert_eq!(model.triangles.len(), original.triangles.len() - 2);
+    assert_eq!(model.groups[3].material_index, -1);
+    assert!(model.validate().is_empty());
+}
@@ -0,0 +1,90 @@
+extern crate ms3d;
+
+use ms3d::{Error, Model, ParseOptions, Section};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+#[test]
+fn test_round_trip() {
+    let model = Model::from_bytes(BYTES).unwrap();
+    let bytes = model.to_bytes().unwrap();
+    assert_eq!(Model::from_bytes(&bytes).unwrap(), model);
+}
+
+#[test]
+fn test_round_trip_exact() {
+    let model = Model::from_bytes(BYTES).unwrap();
+    let mut bytes = Vec::new();
+    model.write_to(&mut bytes).unwrap();
+    assert_eq!(bytes, BYTES);
+}
+
+#[test]
+fn test_write_changed_name() {
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    model.groups[0].name = "caf\u{e9}".to_owned();
+    model.materials[0].name = "x".repeat(33);
+    assert!(model.to_bytes().is_err());
+
+    model.materials[0].name = "\u{3042}".to_owned();
+    assert!(model.to_bytes().is_err());
+
+    model.materials[0].name = "POA5".to_owned();
+    let model = Model::from_bytes(&model.to_bytes().unwrap()).unwrap();
+    assert_eq!(model.groups[0].name, "caf\u{e9}");
+    assert_eq!(model.groups[0].raw_name[..5], b"caf\xe9\0"[..]);
+}
+
+#[test]
+fn test_trailer_round_trip() {
+    let mut bytes = BYTES.to_owned();
+    bytes.extend_from_slice(b"PLUGIN\x01\x02\x03");
+    let model = Model::from_reader(&bytes[..]).unwrap();
+    assert_eq!(model.unknown_trailer, b"PLUGIN\x01\x02\x03");
+    assert_eq!(model.to_bytes().unwrap(), bytes);
+}
+
+#[test]
+fn test_unknown_sub_version_round_trip() {
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    model.vertex_ex_info = None;
+    model.joint_ex_info = None;
+    model.model_ex_info = None;
+    let offset = model.to_bytes().unwrap().len();
+    let mut bytes = BYTES.to_owned();
+    bytes[offset] = 9;
+
+    let options = ParseOptions {
+        strict: false,
+        ..Default::default()
+    };
+    let (model, _) = Model::from_bytes_with(&bytes, options).unwrap();
+    assert!(model.vertex_ex_info.is_none());
+    assert_eq!(model.unknown_trailer, &bytes[offset..]);
+    assert_eq!(model.to_bytes().unwrap(), bytes);
+}
+
+#[test]
+fn test_invalid_sections_write_nothing() {
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    model.comments = None;
+    let mut bytes = Vec::new();
+    match model.write_to(&mut bytes) {
+        Err(Error::MissingSection {
+            section: Section::Comments,
+        }) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(bytes.is_empty());
+
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    model.vertices.pop();
+    match model.write_to(&mut bytes) {
+        Err(Error::LengthMismatch {
+            section: Section::VertexExInfo,
+            ..
+        }) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(bytes.is_empty());
+}
@@ -0,0 +1,147 @@
+extern crate ms3d;
+
+use std::path::PathBuf;
+
+use ms3d::{
+    Comment, IssueKind, Model, ModelBuilder, Section, ValidationIssue, VertexEx2, VertexExInfo,
+};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+#[test]
+fn test_validate() {
+    let model = Model::from_bytes(BYTES).unwrap();
+    assert_eq!(model.validate(), vec![]);
+}
+
+#[test]
+fn test_validate_broken_references() {
+    let mut model = Model::from_bytes(BYTES).unwrap();
+    model.vertices[3].bone_id = 0;
+    model.triangles[5].vertex_indices[1] = 2709;
+    model.triangles[6].group_index = 76;
+    model.groups[7].triangle_indices.push(4756);
+    model.groups[8].material_index = 1;
+
+    assert_eq!(
+        model.validate(),
+        vec![
+            ValidationIssue {
+                section: Section::Vertices,
+                index: 3,
+                kind: IssueKind::BoneId { bone_id: 0 },
+            },
+            ValidationIssue {
+                section: Section::Triangles,
+                index: 5,
+                kind: IssueKind::VertexIndex { vertex_index: 2709 },
+            },
+            ValidationIssue {
+                section: Section::Triangles,
+                index: 6,
+                kind: IssueKind::GroupIndex { group_index: 76 },
+            },
+            ValidationIssue {
+                section: Section::Groups,
+                index: 7,
+                kind: IssueKind::TriangleIndex {
+                    triangle_index: 4756,
+                },
+            },
+            ValidationIssue {
+                section: Section::Groups,
+                index: 8,
+                kind: IssueKind::MaterialIndex { material_index: 1 },
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_validate_joints_and_extra_info() {
+    let mut builder = ModelBuilder::new();
+    let root = builder.add_joint("root", None).unwrap();
+    builder.add_joint("arm", Some(root)).unwrap();
+    let material = builder.add_material("skin", PathBuf::new()).unwrap();
+    builder.add_group("body", Some(material)).unwrap();
+    builder.add_vertex([0.0; 3], Some(root)).unwrap();
+    let mut model = builder.build();
+    assert_eq!(model.validate(), vec![]);
+
+    model.joints[1].parent_name = "hips".to_owned();
+    let comment = |index| Comment {
+        index,
+        comment: String::new(),
+        raw_comment: Vec::new(),
+    };
+    {
+        let comments = model.comments.as_mut().unwrap();
+        comments.group_comments = vec![comment(0), comment(1)];
+        comments.material_comments = vec![comment(0)];
+        comments.joint_comments = vec![comment(-1)];
+    }
+    if let Some(VertexExInfo::SubVersion2(ref mut vertex_ex)) = model.vertex_ex_info {
+        vertex_ex[0].bone_ids = [1, 2, -1];
+        vertex_ex.push(VertexEx2 {
+            bone_ids: [-1; 3],
+            weights: [0; 3],
+            extra: 0,
+        });
+    }
+    model.joint_ex_info.as_mut().unwrap().joint_ex.pop();
+
+    let issues = model.validate();
+    assert_eq!(
+        issues,
+        vec![
+            ValidationIssue {
+                section: Section::Joints,
+                index: 1,
+                kind: IssueKind::ParentName {
+                    parent_name: "hips".to_owned(),
+                },
+            },
+            ValidationIssue {
+                section: Section::Comments,
+                index: 1,
+                kind: IssueKind::CommentIndex {
+                    target: Section::Groups,
+                    index: 1,
+                },
+            },
+            ValidationIssue {
+                section: Section::Comments,
+                index: 0,
+                kind: IssueKind::CommentIndex {
+                    target: Section::Joints,
+                    index: -1,
+                },
+            },
+            ValidationIssue {
+                section: Section::VertexExInfo,
+                index: 0,
+                kind: IssueKind::LengthMismatch {
+                    expected: 1,
+                    found: 2,
+                },
+            },
+            ValidationIssue {
+                section: Section::VertexExInfo,
+                index: 0,
+                kind: IssueKind::BoneId { bone_id: 2 },
+            },
+            ValidationIssue {
+                section: Section::JointExInfo,
+                index: 0,
+                kind: IssueKind::LengthMismatch {
+                    expected: 2,
+                    found: 1,
+                },
+            },
+        ]
+    );
+    assert_eq!(
+        issues[0].to_string(),
+        "element 1 of joints has missing parent joint \"hips\""
+    );
+}
@@ -0,0 +1,122 @@
+extern crate ms3d;
+
+use ms3d::{
+    visit, Comment, Flags, Material, Model, ModelExInfo, ModelVisitor, Section, Triangle, Vertex,
+    VertexEx1, VertexEx2, VertexEx3,
+};
+
+const BYTES: &[u8] = include_bytes!("POA.ms3d");
+
+#[derive(Default)]
+struct Stats {
+    vertices: usize,
+    triangles: usize,
+    groups: Vec<(String, Vec<u16>, i8)>,
+    materials: Vec<String>,
+    group_comments: Vec<Comment>,
+    model_comment: Option<Comment>,
+    vertex_ex: usize,
+    model_ex: bool,
+}
+
+impl ModelVisitor for Stats {
+    fn visit_vertex(&mut self, index: usize, _: &Vertex) {
+        assert_eq!(index, self.vertices);
+        self.vertices += 1;
+    }
+
+    fn visit_triangle(&mut self, _: usize, _: &Triangle) {
+        self.triangles += 1;
+    }
+
+    fn begin_group(&mut self, index: usize, _: Flags, name: &str) {
+        assert_eq!(index, self.groups.len());
+        self.groups.push((name.to_owned(), Vec::new(), -1));
+    }
+
+    fn visit_group_triangle(&mut self, group: usize, triangle_index: u16) {
+        self.groups[group].1.push(triangle_index);
+    }
+
+    fn end_group(&mut self, index: usize, material_index: i8) {
+        self.groups[index].2 = material_index;
+    }
+
+    fn visit_material(&mut self, _: usize, material: &Material) {
+        self.materials.push(material.name.clone());
+    }
+
+    fn visit_comment(&mut self, target: Section, index: usize, comment: &Comment) {
+        if target == Section::Groups {
+            assert_eq!(index, self.group_comments.len());
+            self.group_comments.push(comment.clone());
+        }
+    }
+
+    fn visit_model_comment(&mut self, comment: &Comment) {
+        self.model_comment = Some(comment.clone());
+    }
+
+    fn visit_vertex_ex_1(&mut self, _: usize, _: &VertexEx1) {
+        self.vertex_ex += 1;
+    }
+
+    fn visit_vertex_ex_2(&mut self, _: usize, _: &VertexEx2) {
+        self.vertex_ex += 1;
+    }
+
+    fn visit_vertex_ex_3(&mut self, _: usize, _: &VertexEx3) {
+        self.vertex_ex += 1;
+    }
+
+    fn visit_model_ex_info(&mut self, _: &ModelExInfo) {
+        self.model_ex = true;
+    }
+}
+
+#[test]
+fn test_visit() {
+    let model = Model::from_bytes(BYTES).unwrap();
+    let mut stats = Stats::default();
+    visit(BYTES, &mut stats).unwrap();
+
+    assert_eq!(stats.vertices, model.vertices.len());
+    assert_eq!(stats.triangles, model.triangles.len());
+    assert_eq!(
+        stats.groups,
+        model
+            .groups
+            .iter()
+            .map(|g| (g.name.clone(), g.triangle_indices.clone(), g.material_index))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(stats.materials, vec!["POA5".to_owned()]);
+    assert_eq!(stats.vertex_ex, model.vertices.len());
+    assert!(stats.model_ex);
+
+    let mut model = model;
+    let comment = |index: i32, text: &str| Comment {
+        index,
+        comment: text.to_owned(),
+        raw_comment: text.as_bytes().to_owned(),
+    };
+    {
+        let comments = model.comments.as_mut().unwrap();
+        comments.group_comments = vec![comment(0, "first"), comment(1, "second")];
+        comments.joint_comments = vec![comment(0, "joint")];
+        comments.model_comment = Some(comment(0, "model"));
+    }
+    let mut stats = Stats::default();
+    visit(&model.to_bytes().unwrap()[..], &mut stats).unwrap();
+    let comments = model.comments.unwrap();
+    assert_eq!(stats.group_comments, comments.group_comments);
+    assert_eq!(stats.model_comment, comments.model_comment);
+}
+
+#[test]
+fn test_visit_truncated() {
+    let mut stats = Stats::default();
+    assert!(visit(&BYTES[..1000], &mut stats).is_err());
+    assert!(stats.vertices > 0);
+    assert_eq!(stats.triangles, 0);
+}